@@ -0,0 +1,43 @@
+//! Tab-separated notes for importing into Anki.
+use std::io;
+
+use crate::transcribe::{format_clock_value, Timing};
+
+/// Writes one note per timing with start, end and text fields, plus a `[sound:...]` field when
+/// `audio` is given.
+///
+/// `audio` names each note's audio clip, with `{index}`, `{start}` and `{end}` replaced by the
+/// 1-based note number and the times in milliseconds, e.g. `clip-{index}.mp3`. The header lines
+/// tell Anki how to read the file so no import settings need to be changed.
+pub fn write<W: io::Write>(
+    timings: impl Iterator<Item = Timing>,
+    mut w: W,
+    audio: Option<&str>,
+) -> io::Result<()> {
+    writeln!(w, "#separator:tab")?;
+    writeln!(w, "#html:false")?;
+    if audio.is_some() {
+        writeln!(w, "#columns:Start\tEnd\tText\tAudio")?;
+    } else {
+        writeln!(w, "#columns:Start\tEnd\tText")?;
+    }
+
+    for (i, t) in (1..).zip(timings) {
+        let text = t.content().replace(['\t', '\r', '\n'], " ");
+        write!(
+            w,
+            "{}\t{}\t{text}",
+            format_clock_value(t.start(), None),
+            format_clock_value(t.end(), None)
+        )?;
+        if let Some(template) = audio {
+            let file = template
+                .replace("{index}", &i.to_string())
+                .replace("{start}", &t.start().to_string())
+                .replace("{end}", &t.end().to_string());
+            write!(w, "\t[sound:{file}]")?;
+        }
+        writeln!(w)?;
+    }
+    Ok(())
+}
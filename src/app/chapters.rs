@@ -0,0 +1,76 @@
+//! Chapter lists for video descriptions.
+use std::io;
+
+use super::text::sentence_breaks;
+use crate::transcribe::{SentenceEnds, Timing};
+
+/// Formats whole seconds as `M:SS`, or `H:MM:SS` past an hour, as video descriptions expect.
+fn timestamp(ms: u32) -> String {
+    let s = ms / 1000;
+    let (h, m, s) = (s / 3600, s / 60 % 60, s % 60);
+    if h > 0 {
+        format!("{h}:{m:02}:{s:02}")
+    } else {
+        format!("{m}:{s:02}")
+    }
+}
+
+/// The first sentence of `text`, cut to `max_words` words.
+fn title(text: &str, max_words: usize, ends: &SentenceEnds) -> String {
+    let sentence = sentence_breaks(text, ends)
+        .next()
+        .map_or(text, |end| &text[..end]);
+
+    let words = sentence.split_whitespace().collect::<Vec<_>>();
+    if words.len() > max_words {
+        words[..max_words].join(" ")
+    } else {
+        words.join(" ").trim_end_matches('.').to_string()
+    }
+}
+
+/// Writes a `M:SS Title` line for each timing, titled after its first sentence.
+///
+/// The first chapter is always placed at `0:00`, since chapter lists that start later are
+/// ignored by video sites.
+pub fn write<W: io::Write>(
+    timings: impl Iterator<Item = Timing>,
+    mut w: W,
+    max_words: usize,
+    ends: &SentenceEnds,
+) -> io::Result<()> {
+    for (i, t) in timings.enumerate() {
+        let start = if i == 0 { 0 } else { t.start() };
+        writeln!(
+            w,
+            "{} {}",
+            timestamp(start),
+            title(t.content(), max_words, ends)
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::title;
+    use crate::transcribe::SentenceEnds;
+
+    #[test]
+    fn titles_after_the_first_sentence() {
+        let ends = SentenceEnds::default();
+        assert_eq!(
+            title(" Version 2.5 is out. More later", 8, &ends),
+            "Version 2.5 is out"
+        );
+        assert_eq!(title(" Really? Yes", 8, &ends), "Really?");
+        assert_eq!(title(" 第一章。然后", 8, &ends), "第一章。");
+        assert_eq!(title(" one two three. four", 2, &ends), "one two");
+    }
+
+    #[test]
+    fn titles_after_the_configured_endings() {
+        let ends = SentenceEnds::new(";".chars());
+        assert_eq!(title(" Intro. Setup; then more", 8, &ends), "Intro. Setup;");
+    }
+}
@@ -4,13 +4,17 @@ use std::io;
 
 use clap::Subcommand;
 
-use super::{input, output};
+use super::{
+    anki, chapters, edl, explain, expr, fcpxml, input, output, podcast, retime, stage, text,
+    timecode, tokens, verify, waveform,
+};
 
 #[derive(Debug)]
 pub enum Error {
     Csv(csv::Error),
     Json(serde_json::Error),
     Io(io::Error),
+    Verify(String),
 }
 
 impl From<csv::Error> for Error {
@@ -37,11 +41,13 @@ impl std::fmt::Display for Error {
             Self::Csv(e) => write!(f, "CSV error: {e}"),
             Self::Json(e) => write!(f, "JSON error: {e}"),
             Self::Io(e) => write!(f, "I/O error: {e}"),
+            Self::Verify(msg) => write!(f, "verification failed: {msg}"),
         }
     }
 }
 
 #[derive(Subcommand)]
 pub enum Command {
+    /// Reads a transcript, regroups and cleans up its segments, and writes it in another format
     Transform(transform::Transform),
 }
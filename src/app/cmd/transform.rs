@@ -1,12 +1,42 @@
-use std::{io, time::Duration};
+use std::{
+    cell::RefCell,
+    io::{self, IsTerminal},
+    num::NonZeroUsize,
+    path::PathBuf,
+    time::Duration,
+};
 
 use clap::Args;
 
 use super::{
+    anki, chapters, edl, explain,
+    expr::SplitWhen,
+    fcpxml,
     input::{Input, ParseDuration},
-    output::{Format, Output},
+    output::{Format, Output, Sink},
+    podcast,
+    retime::{self, Overlaps, SyncPoints},
+    stage::Stage,
+    text::{
+        self,
+        case::{self, Case},
+        dictionary::Dictionary,
+        disfluency,
+        glossary::Glossary,
+        numbers,
+        punctuate::PunctuateCmd,
+        replace::Substitution,
+        unicode::Normalization,
+    },
+    timecode::FrameRate,
+    tokens::Tokenizer,
+    verify,
+    waveform::{Peaks, PlayerDocument},
 };
-use crate::transcribe::IterDyn;
+use itertools::Itertools;
+use regex::Regex;
+
+use crate::transcribe::{CountTokens, IterDyn, IteratorExt, SentenceEnds, TimeUnit, Timing};
 
 #[derive(Args)]
 pub struct Transform {
@@ -22,41 +52,487 @@ pub struct Transform {
 
 impl Transform {
     pub fn read_data(&self) -> Result<IterDyn<'_>, io::Error> {
-        use crate::transcribe::IteratorExt;
-
-        let source = self.input.source()?;
-        let raw_iter: IterDyn = self.input.format().consume_reader(source);
-        let timings = raw_iter.join_continuations();
+        self.pipeline.validate()?;
+        if self.input.follow() {
+            let [sink] = self.output.sinks() else {
+                return Err(io::Error::other("--follow writes to a single --output"));
+            };
+            if !self.output.format(sink).streams() {
+                return Err(io::Error::other(
+                    "--follow needs an output format written cue by cue: anki, chapters, csv, jsonl, lrc, srt, vtt, pretty or template",
+                ));
+            }
+            // Clap only sees the flags, not stages given with --pipeline
+            let stages = self.pipeline.merge_stages();
+            if let Some(stage) = stages.iter().find(|s| s.reads_all()) {
+                return Err(io::Error::other(format!(
+                    "--follow can't be combined with {stage}, which reads the whole input first"
+                )));
+            }
+        }
+        self.read_from(self.input.source()?)
+    }
 
+    fn read_from(&self, source: Box<dyn io::Read>) -> io::Result<IterDyn<'_>> {
+        let timings = self
+            .input
+            .format()
+            .consume_reader(source, self.input.options(), |e| self.pipeline.fail(e))?;
         Ok(self.pipeline.process_iter(timings))
     }
 
+    /// The files to convert one by one, if the source was a directory or glob pattern.
+    pub fn batch(&self) -> Option<&[PathBuf]> {
+        self.input.batch()
+    }
+
+    /// Converts each file on its own, writing to the path given by the output template.
+    pub fn process_batch(&self, paths: &[PathBuf]) -> Result<(), super::Error> {
+        if self.pipeline.explain() {
+            return Err(io::Error::other("--explain reads a single source, not a batch").into());
+        }
+        self.pipeline.validate()?;
+
+        for path in paths {
+            let target = self.output.batch_target(path)?;
+            let timings = self.read_from(self.input.open(path)?)?;
+            let written = self.write_to(
+                timings,
+                self.output.batch_format(),
+                self.output.create(&target)?,
+                false,
+            );
+            self.output.finish(&target, written.is_ok())?;
+            written?;
+            eprintln!("{} -> {}", path.display(), target.display());
+        }
+        Ok(())
+    }
+
+    /// Writes to every output, reading the input only once.
     pub fn process_to_output(&self, timings: IterDyn<'_>) -> Result<(), super::Error> {
-        let mut s = self.output.sink()?;
-        match self.output.format() {
-            Format::Csv => timings.write_csv(s)?,
-            Format::Json => timings.write_json(s)?,
-            Format::Srt => timings.write_srt(s)?,
-            Format::Pretty => {
-                for t in timings {
-                    writeln!(s, "{t}\n")?;
+        if self.pipeline.explain() {
+            let written = timings.count();
+            if let Some(e) = self.pipeline.take_failure() {
+                return Err(e.into());
+            }
+            return Ok(self.pipeline.report().write(io::stdout().lock(), written)?);
+        }
+
+        if let [sink] = self.output.sinks() {
+            return self.write_sink(timings, sink);
+        }
+
+        let timings = timings.collect_vec();
+        for sink in self.output.sinks() {
+            self.write_sink(timings.clone().into_iter().boxed(), sink)?;
+        }
+        Ok(())
+    }
+
+    fn write_sink(&self, timings: IterDyn<'_>, sink: &Sink) -> Result<(), super::Error> {
+        let to_terminal = matches!(sink, Sink::Stdout) && io::stdout().is_terminal();
+        let written = self.write_to(
+            timings,
+            self.output.format(sink),
+            self.output.open(sink)?,
+            to_terminal,
+        );
+        if let Some(path) = sink.path() {
+            self.output.finish(path, written.is_ok())?;
+        }
+        written
+    }
+
+    fn write_to(
+        &self,
+        timings: IterDyn<'_>,
+        format: Format,
+        s: Box<dyn io::Write>,
+        to_terminal: bool,
+    ) -> Result<(), super::Error> {
+        let written = self.write_checked(timings, format, s, to_terminal);
+        // A stage that failed ended the segments early, so what was written is incomplete
+        match self.pipeline.take_failure() {
+            Some(e) => Err(e.into()),
+            None => written,
+        }
+    }
+
+    fn write_checked(
+        &self,
+        timings: IterDyn<'_>,
+        format: Format,
+        mut s: Box<dyn io::Write>,
+        to_terminal: bool,
+    ) -> Result<(), super::Error> {
+        if !self.output.verify() {
+            let timings = self.prepare_output(timings, format);
+            return self.write_output(timings, format, s, to_terminal);
+        }
+
+        if self.output.time_unit() == TimeUnit::Seconds {
+            return Err(super::Error::Verify(
+                "output in seconds can't be read back".to_string(),
+            ));
+        }
+
+        // Taken before the output cleanup, so that snapping, sanitizing and wrapping count as loss
+        let expected = timings.collect_vec();
+        if let Some(e) = self.pipeline.take_failure() {
+            return Err(e.into());
+        }
+
+        let mut written = vec![];
+        self.write_output(
+            self.prepare_output(expected.clone().into_iter().boxed(), format),
+            format,
+            &mut written,
+            to_terminal,
+        )?;
+        s.write_all(&written)?;
+        s.flush()?;
+
+        let lossy =
+            verify::lossy_conversions(format, &expected, &written).map_err(super::Error::Verify)?;
+        if lossy.is_empty() {
+            eprintln!(
+                "verify: {} segments round-tripped without loss",
+                expected.len()
+            );
+            return Ok(());
+        }
+
+        for conversion in &lossy {
+            eprintln!("verify: {conversion}");
+        }
+        Err(super::Error::Verify(format!(
+            "{} lossy conversions",
+            lossy.len()
+        )))
+    }
+
+    /// Applies the text cleanup called for by the output format.
+    fn prepare_output<'a>(&self, mut timings: IterDyn<'a>, format: Format) -> IterDyn<'a> {
+        let sanitize = self.output.sanitize(format);
+        timings = timings.map_text(move |text| sanitize.apply(text));
+
+        if let Some(rate) = self.output.frame_rate() {
+            timings = timings.map_times(move |ms| rate.snap(ms));
+        }
+
+        if let Format::Srt | Format::Vtt = format {
+            // Players show stray whitespace and empty cues as-is
+            timings = timings
+                .map_text(text::normalize_space)
+                .filter(|t| !t.text().is_empty())
+                .boxed();
+        }
+
+        if let Some(wrap) = self.output.wrap() {
+            timings = timings.map_text(move |text| wrap.apply(text));
+        }
+
+        if let Format::Srt | Format::Vtt = format {
+            if let Some(marks) = self.output.rtl_marks() {
+                let force = self.output.rtl();
+                timings = timings.map_text(move |text| marks.apply(text, force));
+            }
+        }
+
+        timings
+    }
+
+    fn write_output<W: io::Write>(
+        &self,
+        timings: IterDyn<'_>,
+        format: Format,
+        s: W,
+        to_terminal: bool,
+    ) -> Result<(), super::Error> {
+        let unit = self.output.time_unit();
+        let tokenizer = self.pipeline.tokenizer();
+        let count_tokens = move |text: &str| tokenizer.count(text);
+        let tokens = self
+            .output
+            .token_counts()
+            .then_some(&count_tokens as CountTokens);
+        match format {
+            Format::Anki => anki::write(timings, s, self.output.anki_audio())?,
+            Format::Chapters => {
+                let ends = self.pipeline.sentence_ends();
+                chapters::write(timings, s, self.output.chapter_title_words(), &ends)?;
+            }
+            Format::Csv if self.input.follow() => timings.write_csv_streaming(s, unit)?,
+            Format::Csv => timings.write_csv(s, unit)?,
+            Format::Edl => edl::write(timings, s, self.output.fps())?,
+            Format::Fcpxml => fcpxml::write(timings, s, self.output.fps())?,
+            Format::Html => timings.write_html(s)?,
+            Format::Json if self.output.json_pretty() => {
+                timings.write_json_pretty(s, unit, tokens)?;
+            }
+            Format::Json => timings.write_json(s, unit, tokens)?,
+            Format::JsonLines => timings.write_json_lines(s, unit, tokens)?,
+            Format::Lrc { enhanced } => timings.write_lrc(s, enhanced)?,
+            Format::Srt => timings.write_srt(s, &self.output.srt_options())?,
+            Format::Vtt => timings.write_vtt(s)?,
+            Format::Player => {
+                let waveform = match self.output.media() {
+                    Some(media) => Some(Peaks::from_media(media, self.output.peaks_per_second())?),
+                    None => None,
+                };
+                let document = PlayerDocument {
+                    cues: timings.collect(),
+                    waveform,
+                };
+                if self.output.json_pretty() {
+                    serde_json::to_writer_pretty(s, &document)?;
+                } else {
+                    serde_json::to_writer(s, &document)?;
+                }
+            }
+            Format::Podcast => {
+                let transcript: podcast::Transcript = timings.collect();
+                if self.output.json_pretty() {
+                    serde_json::to_writer_pretty(s, &transcript)?;
+                } else {
+                    serde_json::to_writer(s, &transcript)?;
                 }
             }
-        };
+            Format::Template => {
+                let template = self
+                    .output
+                    .line_template()
+                    .expect("clap requires --template");
+                template.write(timings, s, self.output.fps())?;
+            }
+            Format::Pretty { compact } => {
+                self.output
+                    .pretty_style(to_terminal, compact)
+                    .write(timings, s)?;
+            }
+        }
         Ok(())
     }
 }
 
 #[derive(Args)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct TranscriptionPipeline {
     /// Concatenates until the accumulated delay between events exceeds the given duration.
     #[arg(long, value_parser = ParseDuration)]
     max_silence: Option<Duration>,
 
-    /// Concatenates up to the next sentence ending ('.', '!', or '?')
+    /// Starts a new segment whenever the speaker changes, and never merges segments of
+    /// different speakers.
+    #[arg(long, default_value = "false")]
+    split_speakers: bool,
+
+    /// Writes the silences longer than the given duration instead of what's said, e.g. for a
+    /// list of stretches to skip or trim. Selection options then apply to the silences.
+    #[arg(long, value_name = "DURATION", value_parser = ParseDuration)]
+    invert_gaps: Option<Duration>,
+
+    /// Keeps only the segments of the given speaker, as labeled in the input. May be repeated
+    /// to keep several speakers.
+    #[arg(long = "speaker", value_name = "SPEAKER")]
+    speakers: Vec<String>,
+
+    /// Drops the segments of the given speaker. May be repeated.
+    #[arg(long = "exclude-speaker", value_name = "SPEAKER")]
+    excluded_speakers: Vec<String>,
+
+    /// Keeps every Nth segment of the result, starting with the first, for spot-checking a long
+    /// recording. Combine with --chunk-size to sample longer stretches.
+    #[arg(long, value_name = "N")]
+    every: Option<NonZeroUsize>,
+
+    /// Skips the first N segments of the result (after --every).
+    #[arg(long, value_name = "N")]
+    skip: Option<usize>,
+
+    /// Keeps only the first N segments of the result (after --skip), so a preview of a large
+    /// file stops reading as soon as it has enough.
+    #[arg(long, value_name = "N")]
+    head: Option<usize>,
+
+    /// Keeps only the last N segments of the result (after --skip and --head).
+    #[arg(long, value_name = "N", conflicts_with = "follow")]
+    tail: Option<usize>,
+
+    /// Sorts segments by start time before anything else, for inputs such as merged files or
+    /// separately transcribed channels that aren't in order. Reads the whole input first.
+    #[arg(long, default_value = "false", conflicts_with = "follow")]
+    sort: bool,
+
+    /// Keeps only what's said from the given time on, trimming a segment that starts before.
+    #[arg(long, value_name = "TIME", value_parser = retime::parse_time)]
+    from: Option<u32>,
+
+    /// Keeps only what's said until the given time, trimming a segment that ends after.
+    #[arg(long, value_name = "TIME", value_parser = retime::parse_time)]
+    to: Option<u32>,
+
+    /// Subtracts the start of the first segment from every time, so the transcript starts at
+    /// 0:00. With --from, subtracts that time instead. Applied before any other retiming.
+    #[arg(long, default_value = "false")]
+    rebase: bool,
+
+    /// Corrects progressive drift from two anchors, `t1=t1',t2=t2'`, each pairing a time in the
+    /// transcript with where that moment really is, e.g. `1:00=1:02,50:00=50:30`. Applied
+    /// before --scale and --shift.
+    #[arg(long, value_name = "ANCHORS", value_parser = SyncPoints::parse)]
+    sync_points: Option<SyncPoints>,
+
+    /// Multiplies every time by the given factor, e.g. 1.042709 for a transcript of a copy sped
+    /// up from 23.976 to 25 frames per second. Applied before --shift.
+    #[arg(
+        long,
+        value_name = "FACTOR",
+        conflicts_with = "fps_from",
+        value_parser = retime::parse_factor
+    )]
+    scale: Option<f64>,
+
+    /// The frame rate of the media the transcript was made from, to retime it for the media
+    /// at --fps-to. Both rates are given like --fps.
+    #[arg(long, value_name = "FPS", requires = "fps_to", value_parser = FrameRate::parse)]
+    fps_from: Option<FrameRate>,
+
+    /// The frame rate of the media to retime the transcript for, see --fps-from.
+    #[arg(long, value_name = "FPS", requires = "fps_from", value_parser = FrameRate::parse)]
+    fps_to: Option<FrameRate>,
+
+    /// Moves every time by the given offset, e.g. `+1500ms` or `-2s`, stopping at zero.
+    #[arg(long, value_name = "OFFSET", allow_hyphen_values = true, value_parser = retime::parse_offset)]
+    shift: Option<i64>,
+
+    /// Merges segments overlapping by more than the given duration (any overlap if none is given)
+    /// into one, combining their text, for transcripts made from overlapping windows of audio.
+    /// Runs before --fix-overlaps, which can then clamp the smaller overlaps that remain.
+    #[arg(
+        long,
+        value_name = "DURATION",
+        value_parser = ParseDuration,
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "0ms"
+    )]
+    merge_overlapping: Option<Duration>,
+
+    /// Resolves segments that overlap the next one, which gap-based stages and subtitle players
+    /// don't expect. Clamps each segment's end to the next start unless `merge` is given.
+    #[arg(
+        long,
+        value_name = "HOW",
+        value_enum,
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "clamp"
+    )]
+    fix_overlaps: Option<Overlaps>,
+
+    /// Normalizes text to the given Unicode form and strips control and zero-width characters,
+    /// before anything else looks at it.
+    #[arg(long, value_enum, value_name = "FORM")]
+    normalize_unicode: Option<Normalization>,
+
+    /// Drops segments whose text matches the given regular expression, e.g. `\[Music\]`, before
+    /// any are merged.
+    #[arg(long, value_name = "REGEX", value_parser = Regex::new)]
+    drop_matching: Option<Regex>,
+
+    /// Keeps only the segments whose text matches the given regular expression, with their
+    /// original times. Applied after all merging and splitting.
+    #[arg(long, value_name = "REGEX", value_parser = Regex::new)]
+    keep_matching: Option<Regex>,
+
+    /// Drops segments with fewer than N characters of text, not counting surrounding
+    /// whitespace, such as stray punctuation. Applied after all merging and splitting.
+    #[arg(long, value_name = "N")]
+    min_length: Option<usize>,
+
+    /// Drops segments lasting less than the given duration, such as the brief artifacts some
+    /// recognizers emit. Applied after all merging and splitting.
+    #[arg(long, value_name = "DURATION", value_parser = ParseDuration)]
+    drop_shorter_than: Option<Duration>,
+
+    /// Drops segments the recognizer was less sure of than the given confidence, from 0 to 1.
+    /// Segments without a confidence are kept. Applied after all merging and splitting.
+    #[arg(long, value_name = "CONFIDENCE")]
+    min_confidence: Option<f32>,
+
+    /// Marks segments below --min-confidence with `[?]` instead of dropping them, for review.
+    #[arg(long, default_value = "false", requires = "min_confidence")]
+    mark_low_confidence: bool,
+
+    /// Collapses consecutive repeats of a segment, as produced by whisper hallucination loops,
+    /// into one segment spanning them all.
+    #[arg(long, default_value = "false")]
+    dedupe: bool,
+
+    /// How alike two segments' words must be to count as repeats, from 0 to 1.
+    #[arg(long, default_value = "0.9", requires = "dedupe")]
+    dedupe_similarity: f64,
+
+    /// Merges segments shorter than the given duration into the neighbor closest in time.
+    #[arg(long, value_parser = ParseDuration)]
+    min_duration: Option<Duration>,
+
+    /// Runs the given merging and splitting stages in order, instead of those enabled by their
+    /// own options, e.g. `by-gap=2s,sentences,lasting=8s`. Stages are named like those options,
+    /// and may be repeated.
+    #[arg(
+        long,
+        value_name = "STAGES",
+        value_delimiter = ',',
+        value_parser = Stage::parse,
+        conflicts_with_all = [
+            "min_duration", "max_silence", "by_gap", "gap_percentile", "split_when", "sentences",
+            "clauses", "min_word_count", "lasting", "chunk_size", "max_tokens", "split_sentences",
+            "max_words", "max_chars", "split_lasting", "words", "sentence_cues",
+        ]
+    )]
+    pipeline: Vec<Stage>,
+
+    /// Prints the stages in the order they run, with how many segments went into and came out
+    /// of each, instead of writing output.
+    #[arg(long, default_value = "false", conflicts_with = "follow")]
+    explain: bool,
+
+    #[arg(skip)]
+    report: explain::Report,
+
+    /// The first error from a stage that can fail partway through, which ends the segments.
+    #[arg(skip)]
+    failure: RefCell<Option<io::Error>>,
+
+    /// Concatenates up to the next sentence ending (see --sentence-chars)
     #[arg(short, long, default_value = "false")]
     sentences: bool,
 
+    /// Gives exactly one sentence per segment, merging fragments up to each sentence ending
+    /// like --sentences and splitting segments that hold several like --split-sentences.
+    #[arg(
+        long,
+        default_value = "false",
+        conflicts_with_all = [
+            "clauses", "min_word_count", "lasting", "chunk_size", "max_tokens", "window",
+            "max_words", "max_chars", "split_lasting", "words",
+        ]
+    )]
+    sentence_cues: bool,
+
+    /// The characters that end a sentence, replacing the default of `.!?` and CJK `。！？`. A
+    /// trailing ellipsis only ends a sentence if `…` is listed.
+    #[arg(long, value_name = "CHARS")]
+    sentence_chars: Option<String>,
+
+    /// Like --sentences, but also breaks at commas, semicolons, colons and dashes where a
+    /// segment would otherwise grow past N characters, splitting longer segments there too.
+    #[arg(long, value_name = "N", conflicts_with = "sentences")]
+    clauses: Option<usize>,
+
     /// Concatenates until the total word count of the result exceeds the given value.
     #[arg(short = 'w', long)]
     min_word_count: Option<usize>,
@@ -65,6 +541,18 @@ pub struct TranscriptionPipeline {
     #[arg(short = 'g', long, value_parser = ParseDuration)]
     by_gap: Option<Duration>,
 
+    /// Concatenates segments except where the given condition holds, e.g.
+    /// `gap > 2s || words > 40 || cps > 20`. Conditions compare the `gap` before the next
+    /// segment, or the `duration`, `words`, `chars` or `cps` of the merged segment, and
+    /// `sentence` holds where a sentence ends. Combine them with `&&`, `||`, `!` and parentheses.
+    #[arg(long, value_name = "EXPR", value_parser = SplitWhen::parse)]
+    split_when: Option<SplitWhen>,
+
+    /// Like --by-gap, but works out the duration from the recording, concatenating across all
+    /// but the gaps longer than the given percentage of gaps, e.g. 90.
+    #[arg(long, value_name = "PERCENTILE", conflicts_with_all = ["by_gap", "follow"])]
+    gap_percentile: Option<f64>,
+
     /// Concatenates until the total duration of the result exceeds the given value.
     #[arg(short, long, value_parser = ParseDuration)]
     lasting: Option<Duration>,
@@ -72,38 +560,471 @@ pub struct TranscriptionPipeline {
     /// Concatenates up to N events.
     #[arg(short, long)]
     chunk_size: Option<usize>,
+
+    /// Concatenates segments while they add up to at most N tokens, to fit chunks into a language
+    /// model's context.
+    #[arg(long, value_name = "N")]
+    max_tokens: Option<usize>,
+
+    /// How to count tokens for --max-tokens and --token-counts.
+    #[arg(long, value_enum, default_value_t)]
+    tokenizer: Tokenizer,
+
+    /// Groups segments into chunks lasting up to the given duration, e.g. for summarizing or
+    /// indexing a long recording piece by piece.
+    #[arg(long, value_name = "DURATION", value_parser = ParseDuration)]
+    window: Option<Duration>,
+
+    /// How much of the end of each --window chunk to repeat at the start of the next, so that
+    /// nothing said across a boundary loses its context.
+    #[arg(long, value_name = "DURATION", value_parser = ParseDuration, default_value = "0s", requires = "window")]
+    overlap: Duration,
+
+    /// Splits segments holding more than one sentence into one segment per sentence, dividing
+    /// their time in proportion to the length of each sentence unless word timings are known.
+    #[arg(long, default_value = "false")]
+    split_sentences: bool,
+
+    /// Splits segments of more than N words into parts of nearly equal length.
+    #[arg(long, value_name = "N")]
+    max_words: Option<usize>,
+
+    /// Splits segments longer than N characters at word boundaries, e.g. 84 for two subtitle
+    /// lines of 42.
+    #[arg(long, value_name = "N")]
+    max_chars: Option<usize>,
+
+    /// Splits segments lasting longer than the given duration at word boundaries.
+    #[arg(long, value_parser = ParseDuration)]
+    split_lasting: Option<Duration>,
+
+    /// Splits every segment into one per word, e.g. for karaoke-style output. Uses word timings
+    /// from the input when present, and otherwise divides time by the length of each word.
+    #[arg(long, default_value = "false")]
+    words: bool,
+
+    /// The most characters per second a cue may ask viewers to read. Faster cues are extended
+    /// into the silence after them, or failing that, split at pauses between words.
+    #[arg(long, value_name = "CPS")]
+    max_cps: Option<f64>,
+
+    /// Extends cues shown for less than the given duration into the silence after them, without
+    /// overlapping the next cue, so short subtitles stay up long enough to read.
+    #[arg(long, value_name = "DURATION", value_parser = ParseDuration)]
+    min_display: Option<Duration>,
+
+    /// Corrects out-of-vocabulary words against the given word list (one word per line).
+    #[arg(long, value_name = "FILE", value_parser = Dictionary::parse)]
+    dictionary: Option<Dictionary>,
+
+    /// The maximum number of edits allowed when correcting a word against the dictionary.
+    #[arg(long, default_value = "2", requires = "dictionary")]
+    dictionary_distance: usize,
+
+    /// Applies the substitutions listed in the given CSV file (`wrong,right[,flags]`).
+    #[arg(long, value_name = "FILE", value_parser = Glossary::parse)]
+    glossary: Option<Glossary>,
+
+    /// Rewrites segment text with a sed-style substitution such as `s/kubectl/cube cuddle/gi`.
+    /// May be given more than once; substitutions apply in order.
+    #[arg(long, value_name = "s/PATTERN/REPLACEMENT/FLAGS", value_parser = Substitution::parse)]
+    replace: Vec<Substitution>,
+
+    /// Fixes names and domain terms listed in the given TSV file (`wrong\tright`), matching
+    /// whole words and keeping their capitalization.
+    #[arg(long, value_name = "FILE", value_parser = Glossary::parse_corrections)]
+    corrections: Option<Glossary>,
+
+    /// Collapses words and phrases of up to N words said twice or more in a row ("I I I
+    /// think") into one, and drops stutters such as "th- the". N defaults to 3.
+    #[arg(
+        long,
+        value_name = "N",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "3"
+    )]
+    collapse_repeats: Option<usize>,
+
+    /// Converts spelled-out numbers, ordinals and units to digits ("twenty three percent" to
+    /// "23%"), and years and times of day when the words around them say so ("in nineteen
+    /// ninety" to "in 1990", "at seven thirty" to "at 7:30").
+    #[arg(long, default_value = "false")]
+    normalize_numbers: bool,
+
+    /// Restores punctuation with an external command, run before sentences are detected. The
+    /// command reads one segment per line on stdin and writes one line per segment to stdout.
+    #[arg(long, value_name = "CMD", value_parser = PunctuateCmd::parse)]
+    punctuate_cmd: Option<PunctuateCmd>,
+
+    /// The number of segments sent to each invocation of the punctuation command.
+    #[arg(long, default_value = "200", requires = "punctuate_cmd")]
+    punctuate_batch: NonZeroUsize,
+
+    /// Collapses runs of whitespace within segments and drops segments left empty. Always done
+    /// for SRT output.
+    #[arg(long, default_value = "false")]
+    normalize_space: bool,
+
+    /// Rewrites text in the given case. `sentence` lowercases everything but the start of each
+    /// sentence and the pronoun "I", for all-lowercase or all-caps transcripts.
+    #[arg(long, value_enum)]
+    case: Option<Case>,
+
+    /// Capitalizes the first letter of each segment and of each sentence within it.
+    #[arg(long, default_value = "false")]
+    fix_capitalization: bool,
 }
 
 #[allow(dead_code)]
 impl TranscriptionPipeline {
-    pub fn process_iter<'a>(&self, mut it: IterDyn<'a>) -> IterDyn<'a> {
-        if let Some(silence) = self.max_silence() {
-            it = it.max_silence(silence);
+    /// Rejects combinations of options that clap can't check on its own.
+    pub fn validate(&self) -> io::Result<()> {
+        if self.window().is_some_and(|window| self.overlap() >= window) {
+            return Err(io::Error::other("--overlap must be shorter than --window"));
+        }
+        if let (Some(from), Some(to)) = (self.from(), self.to()) {
+            if from > to {
+                return Err(io::Error::other("--from must not be later than --to"));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn process_iter<'a>(&'a self, mut it: IterDyn<'a>) -> IterDyn<'a> {
+        if self.explain() {
+            let timings = it.collect_vec();
+            self.report.read(timings.len());
+            it = timings.into_iter().boxed();
         }
+        let mut it = self.process_all(it);
 
-        if let Some(gap) = self.by_gap() {
-            it = it.by_gap(gap);
+        if let Some(min_gap) = self.invert_gaps() {
+            it = it.silences(min_gap);
         }
 
-        if self.sentences() {
-            it = it.sentences();
+        // Selection applies to the finished segments, wherever the stages left them
+        if let Some(n) = self.every() {
+            it = it.step_by(n.get()).boxed();
         }
 
-        if let Some(min_word_count) = self.min_word_count() {
-            it = it.min_word_count(min_word_count);
+        if let Some(n) = self.skip() {
+            it = it.skip(n).boxed();
         }
 
-        if let Some(window) = self.lasting() {
-            it = it.lasting(window);
+        if let Some(n) = self.head() {
+            it = it.take(n).boxed();
         }
 
-        if let Some(chunk_count) = self.chunk_size() {
-            it = it.chunks(chunk_count);
+        if let Some(n) = self.tail() {
+            it = it.tail(n);
         }
 
         it
     }
 
+    /// Runs every stage over the whole input, turn by turn if speakers are split.
+    fn process_all<'a>(&'a self, mut it: IterDyn<'a>) -> IterDyn<'a> {
+        // Stages that depend on the whole input run before it's split into speaker turns
+        if self.sort() {
+            it = it.sorted_by_key(Timing::start).boxed();
+        }
+
+        if self.from().is_some() || self.to().is_some() {
+            let from = self.from().unwrap_or(0);
+            it = it.time_range(from, self.to().unwrap_or(u32::MAX));
+        }
+
+        match (self.rebase(), self.from()) {
+            (true, Some(from)) => it = it.map_times(move |ms| ms.saturating_sub(from)),
+            (true, None) => it = it.rebase(),
+            (false, _) => {}
+        }
+
+        if !self.speakers().is_empty() || !self.excluded_speakers().is_empty() {
+            it = it
+                .filter(|t| {
+                    let said_by = |speakers: &[String]| {
+                        t.speaker().is_some_and(|s| speakers.iter().any(|k| k == s))
+                    };
+                    (self.speakers().is_empty() || said_by(self.speakers()))
+                        && !said_by(self.excluded_speakers())
+                })
+                .boxed();
+        }
+
+        if self.split_speakers() {
+            return it
+                .speaker_turns()
+                .flat_map(|turn| self.process_turn(turn.into_iter().boxed()))
+                .boxed();
+        }
+        self.process_turn(it)
+    }
+
+    /// Runs every stage over `it`, which is the whole input unless speakers are split.
+    fn process_turn<'a>(&'a self, it: IterDyn<'a>) -> IterDyn<'a> {
+        self.report.start_turn();
+        let mut it = self.retime(it);
+
+        if let Some(form) = self.normalize_unicode() {
+            it = it.map_text(move |text| form.apply(text));
+        }
+
+        if let Some(pattern) = self.drop_matching() {
+            it = it.filter(|t| !pattern.is_match(t.text())).boxed();
+        }
+
+        if self.dedupe() {
+            let threshold = self.dedupe_similarity();
+            it = it.dedupe_by(move |a, b| text::similarity(a.text(), b.text()) >= threshold);
+        }
+
+        if let Some(dictionary) = self.dictionary() {
+            let distance = self.dictionary_distance();
+            it = it.map_text(move |text| dictionary.correct(text, distance));
+        }
+
+        for stage in self.merge_stages() {
+            it = self.apply_stage(it, stage);
+        }
+
+        if let Some(window) = self.window() {
+            it = it.windows(window, self.overlap());
+        }
+
+        it = self.rewrite_text(it);
+
+        // Splits run last so that text rewrites see whole segments
+        for stage in self.split_stages() {
+            it = self.apply_stage(it, stage);
+        }
+
+        it = self.filter_cues(it);
+
+        // Display timing is adjusted for the cues that are actually written
+        if let Some(cps) = self.max_cps() {
+            it = it.max_cps(cps);
+        }
+
+        if let Some(min_display) = self.min_display() {
+            it = it.min_display(min_display);
+        }
+
+        it
+    }
+
+    /// Drops the finished segments that shouldn't be shown, or marks doubtful ones with
+    /// --mark-low-confidence.
+    fn filter_cues<'a>(&'a self, mut it: IterDyn<'a>) -> IterDyn<'a> {
+        if let Some(pattern) = self.keep_matching() {
+            it = it.filter(|t| pattern.is_match(t.text())).boxed();
+        }
+
+        if let Some(min) = self.min_length() {
+            it = it
+                .filter(move |t| t.content().chars().count() >= min)
+                .boxed();
+        }
+
+        if let Some(min) = self.drop_shorter_than() {
+            let min = u32::try_from(min.as_millis()).unwrap_or(u32::MAX);
+            it = it.filter(move |t| t.duration() >= min).boxed();
+        }
+
+        if let Some(min) = self.min_confidence() {
+            let doubtful = move |t: &Timing| t.confidence().is_some_and(|c| c < min);
+            it = if self.mark_low_confidence() {
+                it.map(move |t| {
+                    if doubtful(&t) {
+                        let text = format!(" [?] {}", t.text().trim_start());
+                        t.with_text(text)
+                    } else {
+                        t
+                    }
+                })
+                .boxed()
+            } else {
+                it.filter(move |t| !doubtful(t)).boxed()
+            };
+        }
+
+        it
+    }
+
+    /// The stages that merge segments, and any that split them when given with --pipeline.
+    ///
+    /// Stages enabled by their own options run in a fixed order, with punctuation restored
+    /// between those that merge by timing and those that look at the text.
+    pub fn merge_stages(&self) -> Vec<Stage> {
+        let punctuate = self.punctuate_cmd().map(|_| Stage::Punctuate);
+        if !self.pipeline.is_empty() {
+            return punctuate.into_iter().chain(self.pipeline.clone()).collect();
+        }
+
+        let mut stages = vec![];
+        stages.extend(self.min_duration().map(Stage::MinDuration));
+        stages.extend(self.max_silence().map(Stage::MaxSilence));
+        stages.extend(self.by_gap().map(Stage::ByGap));
+        stages.extend(self.gap_percentile().map(Stage::GapPercentile));
+        stages.extend(self.split_when().cloned().map(Stage::SplitWhen));
+        stages.extend(punctuate);
+        stages.extend((self.sentences() || self.sentence_cues()).then_some(Stage::Sentences));
+        stages.extend(self.clauses().map(Stage::Clauses));
+        stages.extend(self.min_word_count().map(Stage::MinWordCount));
+        stages.extend(self.lasting().map(Stage::Lasting));
+        stages.extend(self.chunk_size().map(Stage::ChunkSize));
+        stages.extend(self.max_tokens().map(Stage::MaxTokens));
+        stages
+    }
+
+    /// The stages that split segments after their text is rewritten, unless --pipeline
+    /// already placed them.
+    pub fn split_stages(&self) -> Vec<Stage> {
+        if !self.pipeline.is_empty() {
+            return vec![];
+        }
+
+        let mut stages = vec![];
+        stages.extend(
+            (self.split_sentences() || self.sentence_cues()).then_some(Stage::SplitSentences),
+        );
+        stages.extend(self.max_words().map(Stage::MaxWords));
+        stages.extend(self.max_chars().map(Stage::MaxChars));
+        stages.extend(self.split_lasting().map(Stage::SplitLasting));
+        stages.extend(self.words().then_some(Stage::Words));
+        stages
+    }
+
+    /// Runs `stage`, all at once with --explain so that its segments can be counted.
+    fn apply_stage<'a>(&'a self, it: IterDyn<'a>, stage: Stage) -> IterDyn<'a> {
+        if !self.explain() {
+            return self.run_stage(it, stage);
+        }
+
+        let input = it.collect_vec();
+        let count = input.len();
+        let name = stage.to_string();
+        let output = self
+            .run_stage(input.into_iter().boxed(), stage)
+            .collect_vec();
+        self.report.record(|| name, count, output.len());
+        output.into_iter().boxed()
+    }
+
+    fn run_stage<'a>(&'a self, it: IterDyn<'a>, stage: Stage) -> IterDyn<'a> {
+        match stage {
+            Stage::MinDuration(min_duration) => it.min_duration(min_duration),
+            Stage::MaxSilence(silence) => it.max_silence(silence),
+            Stage::ByGap(gap) => it.by_gap(gap),
+            Stage::GapPercentile(percentile) => it.by_gap_percentile(percentile),
+            Stage::Punctuate => match self.punctuate_cmd() {
+                Some(cmd) => it.map_text_batched(
+                    self.punctuate_batch(),
+                    |texts| cmd.run(texts),
+                    |e| self.fail(e),
+                ),
+                None => it,
+            },
+            Stage::SplitWhen(condition) => {
+                let ends = self.sentence_ends();
+                it.split_when(move |acc, next| condition.splits(acc, next, &ends))
+            }
+            Stage::Sentences => it.sentences(self.sentence_ends()),
+            Stage::Clauses(max_chars) => it.clauses(max_chars, self.sentence_ends()),
+            Stage::MinWordCount(min_word_count) => it.min_word_count(min_word_count),
+            Stage::Lasting(window) => it.lasting(window),
+            Stage::ChunkSize(chunk_count) => it.chunks(chunk_count),
+            Stage::MaxTokens(max) => {
+                let tokenizer = self.tokenizer();
+                it.max_tokens(max, move |text| tokenizer.count(text))
+            }
+            Stage::SplitSentences => it.split_sentences(self.sentence_ends()),
+            Stage::MaxWords(max_words) => it.max_words(max_words),
+            Stage::MaxChars(max_chars) => it.max_chars(max_chars),
+            Stage::SplitLasting(max_duration) => it.split_lasting(max_duration),
+            Stage::Words => it.words(),
+        }
+    }
+
+    /// Rewrites the text of segments. Runs after merging so that multi-word entries can match
+    /// across events.
+    fn rewrite_text<'a>(&'a self, mut it: IterDyn<'a>) -> IterDyn<'a> {
+        if let Some(glossary) = self.glossary() {
+            it = it.map_text(|text| glossary.apply(text));
+        }
+
+        if let Some(corrections) = self.corrections() {
+            it = it.map_text(|text| corrections.apply(text));
+        }
+
+        for substitution in self.replace() {
+            it = it.map_text(|text| substitution.apply(text));
+        }
+
+        if let Some(max_len) = self.collapse_repeats() {
+            it = it.map_text(move |text| disfluency::collapse_repeats(text, max_len));
+        }
+
+        if self.normalize_numbers() {
+            it = it.map_text(numbers::normalize);
+        }
+
+        if let Some(case) = self.case() {
+            let ends = self.sentence_ends();
+            it = it.map_text(move |text| case.apply(text, &ends));
+        }
+
+        if self.fix_capitalization() {
+            let ends = self.sentence_ends();
+            it = it.map_text(move |text| case::capitalize_sentences(text, &ends));
+        }
+
+        if self.normalize_space() {
+            it = it
+                .map_text(text::normalize_space)
+                .filter(|t| !t.text().is_empty())
+                .boxed();
+        }
+
+        it
+    }
+
+    /// Corrects the times of segments, before anything else relies on them.
+    fn retime<'a>(&self, mut it: IterDyn<'a>) -> IterDyn<'a> {
+        if let Some(sync) = self.sync_points() {
+            it = it.map_times(move |ms| sync.apply(ms));
+        }
+
+        if let Some(factor) = self.scale() {
+            it = it.map_times(move |ms| retime::scale(ms, factor));
+        }
+
+        if let Some(offset) = self.shift() {
+            it = it.map_times(move |ms| retime::shift(ms, offset));
+        }
+
+        if let Some(min_overlap) = self.merge_overlapping() {
+            it = it.merge_overlapping(min_overlap);
+        }
+
+        match self.fix_overlaps() {
+            Some(Overlaps::Clamp) => it = it.clamp_overlaps(),
+            Some(Overlaps::Merge) => it = it.merge_overlapping(Duration::ZERO),
+            None => {}
+        }
+
+        it
+    }
+
+    pub fn max_cps(&self) -> Option<f64> {
+        self.max_cps
+    }
+
+    pub fn min_display(&self) -> Option<Duration> {
+        self.min_display
+    }
+
     pub fn max_silence(&self) -> Option<Duration> {
         self.max_silence
     }
@@ -116,15 +1037,256 @@ impl TranscriptionPipeline {
         self.by_gap
     }
 
+    pub fn split_when(&self) -> Option<&SplitWhen> {
+        self.split_when.as_ref()
+    }
+
+    pub fn gap_percentile(&self) -> Option<f64> {
+        self.gap_percentile
+    }
+
     pub fn lasting(&self) -> Option<Duration> {
         self.lasting
     }
 
+    pub fn max_tokens(&self) -> Option<usize> {
+        self.max_tokens
+    }
+
+    pub fn tokenizer(&self) -> Tokenizer {
+        self.tokenizer
+    }
+
+    pub fn window(&self) -> Option<Duration> {
+        self.window
+    }
+
+    pub fn overlap(&self) -> Duration {
+        self.overlap
+    }
+
     pub fn chunk_size(&self) -> Option<usize> {
         self.chunk_size
     }
 
+    pub fn sentence_cues(&self) -> bool {
+        self.sentence_cues
+    }
+
+    pub fn split_sentences(&self) -> bool {
+        self.split_sentences
+    }
+
+    pub fn max_words(&self) -> Option<usize> {
+        self.max_words
+    }
+
+    pub fn max_chars(&self) -> Option<usize> {
+        self.max_chars
+    }
+
+    pub fn words(&self) -> bool {
+        self.words
+    }
+
+    pub fn split_lasting(&self) -> Option<Duration> {
+        self.split_lasting
+    }
+
+    pub fn speakers(&self) -> &[String] {
+        &self.speakers
+    }
+
+    pub fn excluded_speakers(&self) -> &[String] {
+        &self.excluded_speakers
+    }
+
+    pub fn invert_gaps(&self) -> Option<Duration> {
+        self.invert_gaps
+    }
+
+    pub fn every(&self) -> Option<NonZeroUsize> {
+        self.every
+    }
+
+    pub fn skip(&self) -> Option<usize> {
+        self.skip
+    }
+
+    pub fn head(&self) -> Option<usize> {
+        self.head
+    }
+
+    pub fn tail(&self) -> Option<usize> {
+        self.tail
+    }
+
+    pub fn sort(&self) -> bool {
+        self.sort
+    }
+
+    pub fn from(&self) -> Option<u32> {
+        self.from
+    }
+
+    pub fn to(&self) -> Option<u32> {
+        self.to
+    }
+
+    pub fn rebase(&self) -> bool {
+        self.rebase
+    }
+
+    pub fn sync_points(&self) -> Option<SyncPoints> {
+        self.sync_points
+    }
+
+    /// The factor to multiply times by, given directly or as a pair of frame rates.
+    pub fn scale(&self) -> Option<f64> {
+        let rates = self.fps_from.zip(self.fps_to);
+        self.scale
+            .or_else(|| rates.map(|(from, to)| from.per_second() / to.per_second()))
+    }
+
+    pub fn shift(&self) -> Option<i64> {
+        self.shift
+    }
+
+    pub fn merge_overlapping(&self) -> Option<Duration> {
+        self.merge_overlapping
+    }
+
+    pub fn fix_overlaps(&self) -> Option<Overlaps> {
+        self.fix_overlaps
+    }
+
+    pub fn normalize_unicode(&self) -> Option<Normalization> {
+        self.normalize_unicode
+    }
+
+    pub fn drop_matching(&self) -> Option<&Regex> {
+        self.drop_matching.as_ref()
+    }
+
+    pub fn keep_matching(&self) -> Option<&Regex> {
+        self.keep_matching.as_ref()
+    }
+
+    pub fn min_length(&self) -> Option<usize> {
+        self.min_length
+    }
+
+    pub fn drop_shorter_than(&self) -> Option<Duration> {
+        self.drop_shorter_than
+    }
+
+    pub fn min_confidence(&self) -> Option<f32> {
+        self.min_confidence
+    }
+
+    pub fn mark_low_confidence(&self) -> bool {
+        self.mark_low_confidence
+    }
+
+    pub fn dedupe(&self) -> bool {
+        self.dedupe
+    }
+
+    pub fn dedupe_similarity(&self) -> f64 {
+        self.dedupe_similarity
+    }
+
+    pub fn split_speakers(&self) -> bool {
+        self.split_speakers
+    }
+
+    pub fn min_duration(&self) -> Option<Duration> {
+        self.min_duration
+    }
+
+    pub fn pipeline(&self) -> &[Stage] {
+        &self.pipeline
+    }
+
+    pub fn explain(&self) -> bool {
+        self.explain
+    }
+
+    pub fn report(&self) -> &explain::Report {
+        &self.report
+    }
+
+    /// Records the error that ended the segments early, keeping the first one.
+    fn fail(&self, e: io::Error) {
+        self.failure.borrow_mut().get_or_insert(e);
+    }
+
+    /// Returns the error that ended the segments early, if any.
+    pub fn take_failure(&self) -> Option<io::Error> {
+        self.failure.take()
+    }
+
+    pub fn sentence_ends(&self) -> SentenceEnds {
+        self.sentence_chars
+            .as_deref()
+            .map_or_else(SentenceEnds::default, |chars| {
+                SentenceEnds::new(chars.chars())
+            })
+    }
+
+    pub fn clauses(&self) -> Option<usize> {
+        self.clauses
+    }
+
     pub fn sentences(&self) -> bool {
         self.sentences
     }
+
+    pub fn dictionary(&self) -> Option<&Dictionary> {
+        self.dictionary.as_ref()
+    }
+
+    pub fn dictionary_distance(&self) -> usize {
+        self.dictionary_distance
+    }
+
+    pub fn glossary(&self) -> Option<&Glossary> {
+        self.glossary.as_ref()
+    }
+
+    pub fn replace(&self) -> &[Substitution] {
+        &self.replace
+    }
+
+    pub fn corrections(&self) -> Option<&Glossary> {
+        self.corrections.as_ref()
+    }
+
+    pub fn collapse_repeats(&self) -> Option<usize> {
+        self.collapse_repeats
+    }
+
+    pub fn normalize_numbers(&self) -> bool {
+        self.normalize_numbers
+    }
+
+    pub fn punctuate_cmd(&self) -> Option<&PunctuateCmd> {
+        self.punctuate_cmd.as_ref()
+    }
+
+    pub fn punctuate_batch(&self) -> NonZeroUsize {
+        self.punctuate_batch
+    }
+
+    pub fn normalize_space(&self) -> bool {
+        self.normalize_space
+    }
+
+    pub fn case(&self) -> Option<Case> {
+        self.case
+    }
+
+    pub fn fix_capitalization(&self) -> bool {
+        self.fix_capitalization
+    }
 }
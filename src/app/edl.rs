@@ -0,0 +1,45 @@
+//! CMX 3600 edit decision lists.
+use std::io;
+
+use super::timecode::FrameRate;
+use crate::transcribe::Timing;
+
+/// Writes each timing as a cut from source reel `AX`, placed back to back on the record side,
+/// with its text as a comment.
+///
+/// Boundaries are rounded to the nearest frame, and every event lasts at least one frame.
+pub fn write<W: io::Write>(
+    timings: impl Iterator<Item = Timing>,
+    mut w: W,
+    rate: FrameRate,
+) -> io::Result<()> {
+    writeln!(w, "TITLE: Transcript")?;
+    let fcm = if rate.drop_frame() {
+        "DROP FRAME"
+    } else {
+        "NON-DROP FRAME"
+    };
+    writeln!(w, "FCM: {fcm}")?;
+
+    let mut record = 0;
+    for (i, t) in (1..).zip(timings) {
+        let source_in = rate.frames(t.start());
+        let source_out = rate.frames(t.end()).max(source_in + 1);
+        let record_out = record + (source_out - source_in);
+
+        writeln!(w)?;
+        writeln!(
+            w,
+            "{i:03}  AX       V     C        {} {} {} {}",
+            rate.timecode(source_in),
+            rate.timecode(source_out),
+            rate.timecode(record),
+            rate.timecode(record_out)
+        )?;
+        // Comments are a single line each
+        writeln!(w, "* COMMENT: {}", t.content().replace(['\r', '\n'], " "))?;
+
+        record = record_out;
+    }
+    Ok(())
+}
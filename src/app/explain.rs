@@ -0,0 +1,79 @@
+//! Counting the segments that go into and come out of each stage, for `--explain`.
+use std::{
+    cell::{Cell, RefCell},
+    io,
+};
+
+/// The stages that ran, in order, with how many segments each was given and gave back.
+///
+/// When speakers are split, every turn runs the same stages, and their counts are summed.
+#[derive(Debug, Default)]
+pub struct Report {
+    read: Cell<usize>,
+    stages: RefCell<Vec<Count>>,
+    /// The index the next stage is recorded at
+    next: Cell<usize>,
+    /// The index of the first stage run for each turn
+    turn_start: Cell<Option<usize>>,
+}
+
+#[derive(Debug)]
+struct Count {
+    stage: String,
+    input: usize,
+    output: usize,
+}
+
+impl Report {
+    pub fn read(&self, segments: usize) {
+        self.read.set(segments);
+    }
+
+    /// Goes back to the first stage, so that the next turn adds to the same counts.
+    pub fn start_turn(&self) {
+        match self.turn_start.get() {
+            Some(start) => self.next.set(start),
+            None => self.turn_start.set(Some(self.next.get())),
+        }
+    }
+
+    pub fn record(&self, stage: impl FnOnce() -> String, input: usize, output: usize) {
+        let index = self.next.replace(self.next.get() + 1);
+        let mut stages = self.stages.borrow_mut();
+        match stages.get_mut(index) {
+            Some(count) => {
+                count.input += input;
+                count.output += output;
+            }
+            None => stages.push(Count {
+                stage: stage(),
+                input,
+                output,
+            }),
+        }
+    }
+
+    /// Writes one line per stage between the segments read and those that would be written.
+    pub fn write<W: io::Write>(&self, mut w: W, written: usize) -> io::Result<()> {
+        let stages = self.stages.borrow();
+        let width = stages.iter().map(|c| c.stage.len()).max().unwrap_or(0);
+        let digits = stages
+            .iter()
+            .map(|c| c.input)
+            .chain([self.read.get()])
+            .max()
+            .unwrap_or(0)
+            .to_string()
+            .len();
+
+        writeln!(w, "read {} segments", self.read.get())?;
+        for count in stages.iter() {
+            writeln!(
+                w,
+                "  {:width$}  {:>digits$} -> {}",
+                count.stage, count.input, count.output
+            )?;
+        }
+        writeln!(w, "would write {written} segments")
+    }
+}
@@ -0,0 +1,393 @@
+//! The expression language of `--split-when`, e.g. `gap > 2s || words > 40 || cps > 20`.
+//!
+//! An expression compares properties of a candidate merge, of the segment built so far with
+//! the next one, and combines comparisons with `&&`, `||`, `!` and parentheses.
+use std::{fmt, iter::Peekable, vec::IntoIter};
+
+use crate::transcribe::{SentenceEnds, Timing};
+
+/// A number measured on a candidate merge.
+#[derive(Debug, Clone, Copy)]
+enum Var {
+    /// The silence between the two segments
+    Gap,
+    /// How long the merged segment would last
+    Duration,
+    /// The words in the merged segment
+    Words,
+    /// The characters in the merged segment
+    Chars,
+    /// The characters per second of the merged segment
+    Cps,
+}
+
+impl Var {
+    fn parse(name: &str) -> Option<Self> {
+        Some(match name {
+            "gap" => Self::Gap,
+            "duration" => Self::Duration,
+            "words" => Self::Words,
+            "chars" => Self::Chars,
+            "cps" => Self::Cps,
+            _ => return None,
+        })
+    }
+
+    fn is_duration(self) -> bool {
+        matches!(self, Self::Gap | Self::Duration)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+}
+
+impl Op {
+    fn apply(self, a: f64, b: f64) -> bool {
+        match self {
+            Self::Lt => a < b,
+            Self::Le => a <= b,
+            Self::Gt => a > b,
+            Self::Ge => a >= b,
+            Self::Eq => (a - b).abs() < f64::EPSILON,
+            Self::Ne => (a - b).abs() >= f64::EPSILON,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    /// A number, in milliseconds if it was given with a unit
+    Number {
+        value: f64,
+        duration: bool,
+    },
+    Op(Op),
+    And,
+    Or,
+    Not,
+    Open,
+    Close,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let symbol = match self {
+            Self::Ident(name) => return write!(f, "{name:?}"),
+            Self::Number {
+                value,
+                duration: true,
+            } => return write!(f, "{value}ms"),
+            Self::Number {
+                value,
+                duration: false,
+            } => return write!(f, "{value}"),
+            Self::Op(Op::Lt) => "<",
+            Self::Op(Op::Le) => "<=",
+            Self::Op(Op::Gt) => ">",
+            Self::Op(Op::Ge) => ">=",
+            Self::Op(Op::Eq) => "==",
+            Self::Op(Op::Ne) => "!=",
+            Self::And => "&&",
+            Self::Or => "||",
+            Self::Not => "!",
+            Self::Open => "(",
+            Self::Close => ")",
+        };
+        write!(f, "'{symbol}'")
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    Or(Box<Expr>, Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Compare(Var, Op, f64),
+    /// Whether the segment built so far ends a sentence
+    Sentence,
+}
+
+/// The properties of merging `acc` with `next` that an expression can refer to.
+struct Candidate {
+    gap: f64,
+    duration: f64,
+    words: f64,
+    chars: f64,
+    sentence: bool,
+}
+
+impl Candidate {
+    #[allow(clippy::cast_precision_loss)]
+    fn new(acc: &Timing, next: &Timing, ends: &SentenceEnds) -> Self {
+        let count = |t: &Timing| t.content().chars().count();
+        Self {
+            gap: f64::from(next.start().saturating_sub(acc.end())),
+            duration: f64::from(next.end().max(acc.end()).saturating_sub(acc.start())),
+            words: (acc.text().split_whitespace().count() + next.text().split_whitespace().count())
+                as f64,
+            chars: (count(acc) + count(next) + 1) as f64,
+            sentence: ends.is_end(acc.text()),
+        }
+    }
+
+    fn get(&self, var: Var) -> f64 {
+        match var {
+            Var::Gap => self.gap,
+            Var::Duration => self.duration,
+            Var::Words => self.words,
+            Var::Chars => self.chars,
+            Var::Cps if self.duration > 0.0 => self.chars * 1000.0 / self.duration,
+            Var::Cps => f64::INFINITY,
+        }
+    }
+}
+
+impl Expr {
+    fn eval(&self, c: &Candidate) -> bool {
+        match self {
+            Self::Or(a, b) => a.eval(c) || b.eval(c),
+            Self::And(a, b) => a.eval(c) && b.eval(c),
+            Self::Not(a) => !a.eval(c),
+            Self::Compare(var, op, value) => op.apply(c.get(*var), *value),
+            Self::Sentence => c.sentence,
+        }
+    }
+}
+
+/// A condition under which consecutive segments are kept apart.
+///
+/// Comparisons may refer to `gap` and `duration`, which take values such as `2s` or `500ms`,
+/// and to `words`, `chars` and `cps` (characters per second) of the merged segment. `sentence`
+/// is true when the segment built so far ends a sentence.
+#[derive(Debug, Clone)]
+pub struct SplitWhen {
+    expr: Expr,
+    /// The expression as it was given, for --explain
+    source: String,
+}
+
+impl SplitWhen {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let mut tokens = tokenize(s)?.into_iter().peekable();
+        let expr = parse_or(&mut tokens)?;
+        match tokens.next() {
+            None => Ok(Self {
+                expr,
+                source: s.trim().to_string(),
+            }),
+            Some(token) => Err(format!("unexpected {token} in {s:?}")),
+        }
+    }
+
+    /// Whether `next` should start a new segment rather than join `acc`.
+    pub fn splits(&self, acc: &Timing, next: &Timing, ends: &SentenceEnds) -> bool {
+        self.expr.eval(&Candidate::new(acc, next, ends))
+    }
+}
+
+impl fmt::Display for SplitWhen {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+fn tokenize(s: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = vec![];
+    let mut chars = s.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+
+        if c.is_ascii_digit() || c == '.' {
+            let mut number = String::new();
+            while let Some(d) = chars.next_if(|d| d.is_ascii_digit() || *d == '.') {
+                number.push(d);
+            }
+            let mut unit = String::new();
+            while let Some(u) = chars.next_if(char::is_ascii_alphabetic) {
+                unit.push(u);
+            }
+
+            let value: f64 = number
+                .parse()
+                .map_err(|_| format!("invalid number {number:?}"))?;
+            tokens.push(match unit.as_str() {
+                "" => Token::Number {
+                    value,
+                    duration: false,
+                },
+                "ms" => Token::Number {
+                    value,
+                    duration: true,
+                },
+                "s" => Token::Number {
+                    value: value * 1000.0,
+                    duration: true,
+                },
+                _ => return Err(format!("unknown unit {unit:?}; expected 's' or 'ms'")),
+            });
+            continue;
+        }
+
+        if c.is_ascii_alphabetic() || c == '_' {
+            let mut ident = String::new();
+            while let Some(c) = chars.next_if(|c| c.is_ascii_alphanumeric() || *c == '_') {
+                ident.push(c);
+            }
+            tokens.push(Token::Ident(ident));
+            continue;
+        }
+
+        chars.next();
+        let followed_by_eq = chars.next_if_eq(&'=').is_some();
+        tokens.push(match (c, followed_by_eq) {
+            ('<', false) => Token::Op(Op::Lt),
+            ('<', true) => Token::Op(Op::Le),
+            ('>', false) => Token::Op(Op::Gt),
+            ('>', true) => Token::Op(Op::Ge),
+            ('=', true) => Token::Op(Op::Eq),
+            ('!', true) => Token::Op(Op::Ne),
+            ('!', false) => Token::Not,
+            ('&', false) if chars.next_if_eq(&'&').is_some() => Token::And,
+            ('|', false) if chars.next_if_eq(&'|').is_some() => Token::Or,
+            ('(', false) => Token::Open,
+            (')', false) => Token::Close,
+            _ => return Err(format!("unexpected {c:?} in {s:?}")),
+        });
+    }
+    Ok(tokens)
+}
+
+type Tokens = Peekable<IntoIter<Token>>;
+
+fn parse_or(tokens: &mut Tokens) -> Result<Expr, String> {
+    let mut expr = parse_and(tokens)?;
+    while tokens.next_if_eq(&Token::Or).is_some() {
+        expr = Expr::Or(Box::new(expr), Box::new(parse_and(tokens)?));
+    }
+    Ok(expr)
+}
+
+fn parse_and(tokens: &mut Tokens) -> Result<Expr, String> {
+    let mut expr = parse_not(tokens)?;
+    while tokens.next_if_eq(&Token::And).is_some() {
+        expr = Expr::And(Box::new(expr), Box::new(parse_not(tokens)?));
+    }
+    Ok(expr)
+}
+
+fn parse_not(tokens: &mut Tokens) -> Result<Expr, String> {
+    if tokens.next_if_eq(&Token::Not).is_some() {
+        return Ok(Expr::Not(Box::new(parse_not(tokens)?)));
+    }
+
+    match tokens.next() {
+        Some(Token::Open) => {
+            let expr = parse_or(tokens)?;
+            match tokens.next() {
+                Some(Token::Close) => Ok(expr),
+                _ => Err("expected ')'".to_string()),
+            }
+        }
+        Some(Token::Ident(name)) if name == "sentence" => Ok(Expr::Sentence),
+        Some(Token::Ident(name)) => {
+            let var = Var::parse(&name).ok_or_else(|| {
+                format!(
+                    "unknown name {name:?}; expected gap, duration, words, chars, cps or sentence"
+                )
+            })?;
+            let Some(Token::Op(op)) = tokens.next() else {
+                return Err(format!("expected a comparison after {name:?}"));
+            };
+            let Some(Token::Number { value, duration }) = tokens.next() else {
+                return Err(format!("expected a number to compare {name:?} with"));
+            };
+            match (var.is_duration(), duration) {
+                (true, false) => Err(format!("{name} takes a duration such as 2s or 500ms")),
+                (false, true) => Err(format!("{name} takes a plain number")),
+                _ => Ok(Expr::Compare(var, op, value)),
+            }
+        }
+        Some(token) => Err(format!("unexpected {token}")),
+        None => Err("unexpected end of expression".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SplitWhen;
+    use crate::transcribe::{SentenceEnds, Timing};
+
+    fn splits(expr: &str, acc: &Timing, next: &Timing) -> bool {
+        SplitWhen::parse(expr)
+            .unwrap()
+            .splits(acc, next, &SentenceEnds::default())
+    }
+
+    #[test]
+    fn compares_durations_and_counts() {
+        let acc = Timing::new(0, 1000, "one two".to_string());
+        let next = Timing::new(3000, 4000, " three".to_string());
+        assert!(splits("gap > 1500ms", &acc, &next));
+        assert!(!splits("gap > 2s", &acc, &next));
+        assert!(splits("duration >= 4s", &acc, &next));
+        assert!(splits("words == 3", &acc, &next));
+        assert!(splits("chars != 14", &acc, &next));
+        assert!(!splits("cps > 5", &acc, &next));
+    }
+
+    #[test]
+    fn combines_with_precedence() {
+        let acc = Timing::new(0, 1000, "Done.".to_string());
+        let next = Timing::new(1000, 2000, " Next".to_string());
+        assert!(splits("sentence", &acc, &next));
+        assert!(splits("gap > 1s || words < 3 && sentence", &acc, &next));
+        assert!(!splits("(gap > 1s || words < 3) && !sentence", &acc, &next));
+        assert!(!splits("!!(words > 2)", &acc, &next));
+    }
+
+    #[test]
+    fn keeps_the_source_for_display() {
+        let expr = SplitWhen::parse(" gap > 2s || cps > 20 ").unwrap();
+        assert_eq!(expr.to_string(), "gap > 2s || cps > 20");
+    }
+
+    #[test]
+    fn explains_malformed_expressions() {
+        let err = |s| SplitWhen::parse(s).unwrap_err();
+        assert_eq!(err(""), "unexpected end of expression");
+        assert_eq!(err("gap > 2s &&"), "unexpected end of expression");
+        assert_eq!(err("gap"), r#"expected a comparison after "gap""#);
+        assert_eq!(err("gap >"), r#"expected a number to compare "gap" with"#);
+        assert_eq!(err("gap > 2"), "gap takes a duration such as 2s or 500ms");
+        assert_eq!(err("words > 2s"), "words takes a plain number");
+        assert_eq!(err("gap > 2m"), r#"unknown unit "m"; expected 's' or 'ms'"#);
+        assert_eq!(
+            err("pace > 2"),
+            r#"unknown name "pace"; expected gap, duration, words, chars, cps or sentence"#
+        );
+        assert_eq!(err("(gap > 2s"), "expected ')'");
+        assert_eq!(
+            err("gap > 2s | words > 3"),
+            r#"unexpected '|' in "gap > 2s | words > 3""#
+        );
+        assert_eq!(err("words = 3"), r#"unexpected '=' in "words = 3""#);
+        assert_eq!(err("words > 1.2.3"), r#"invalid number "1.2.3""#);
+        assert_eq!(err("gap > 2s)"), r#"unexpected ')' in "gap > 2s)""#);
+        assert_eq!(err("gap > 2s && || words > 3"), "unexpected '||'");
+        assert_eq!(
+            err("gap > 2s words"),
+            r#"unexpected "words" in "gap > 2s words""#
+        );
+    }
+}
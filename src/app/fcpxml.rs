@@ -0,0 +1,81 @@
+//! Final Cut Pro XML with one marker per segment.
+use std::io;
+
+use super::timecode::FrameRate;
+use crate::transcribe::Timing;
+
+const VERSION: &str = "1.9";
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace(['\r', '\n'], " ")
+}
+
+/// Writes a project whose timeline is a single gap spanning the transcript, marked at the start
+/// of each timing with its text.
+///
+/// Editors can paste the gap above their footage, or import the markers with tools that copy
+/// them between clips. Marker times are rounded to the nearest frame.
+pub fn write<W: io::Write>(
+    timings: impl Iterator<Item = Timing>,
+    mut w: W,
+    rate: FrameRate,
+) -> io::Result<()> {
+    let markers = timings
+        .map(|t| {
+            let start = rate.frames(t.start());
+            let duration = rate.frames(t.end()).saturating_sub(start).max(1);
+            (start, duration, escape(t.content()))
+        })
+        .collect::<Vec<_>>();
+    let length = markers
+        .iter()
+        .map(|(start, d, _)| start + d)
+        .max()
+        .unwrap_or(1);
+
+    let tc_format = if rate.drop_frame() { "DF" } else { "NDF" };
+    writeln!(w, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(w, "<!DOCTYPE fcpxml>")?;
+    writeln!(w, r#"<fcpxml version="{VERSION}">"#)?;
+    writeln!(w, "  <resources>")?;
+    writeln!(
+        w,
+        r#"    <format id="r1" frameDuration="{}"/>"#,
+        rate.rational(1)
+    )?;
+    writeln!(w, "  </resources>")?;
+    writeln!(w, "  <library>")?;
+    writeln!(w, r#"    <event name="Transcript">"#)?;
+    writeln!(w, r#"      <project name="Transcript">"#)?;
+    writeln!(
+        w,
+        r#"        <sequence format="r1" duration="{}" tcStart="0s" tcFormat="{tc_format}">"#,
+        rate.rational(length)
+    )?;
+    writeln!(w, "          <spine>")?;
+    writeln!(
+        w,
+        r#"            <gap name="Transcript" offset="0s" start="0s" duration="{}">"#,
+        rate.rational(length)
+    )?;
+    for (start, duration, text) in markers {
+        writeln!(
+            w,
+            r#"              <marker start="{}" duration="{}" value="{text}"/>"#,
+            rate.rational(start),
+            rate.rational(duration)
+        )?;
+    }
+    writeln!(w, "            </gap>")?;
+    writeln!(w, "          </spine>")?;
+    writeln!(w, "        </sequence>")?;
+    writeln!(w, "      </project>")?;
+    writeln!(w, "    </event>")?;
+    writeln!(w, "  </library>")?;
+    writeln!(w, "</fcpxml>")?;
+    Ok(())
+}
@@ -0,0 +1,78 @@
+use crate::transcribe::{ms_from_seconds, Timing};
+
+/// Parses a time-marked conversation (CTM) file into one `Timing` per word.
+///
+/// Each line is `utterance channel start duration word [confidence]`, with times in seconds.
+/// Lines starting with `;;` are comments.
+pub fn parse(content: &str) -> Result<Vec<Timing>, String> {
+    let mut timings = vec![];
+
+    for (i, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with(";;") {
+            continue;
+        }
+
+        let fields = line.split_whitespace().collect::<Vec<_>>();
+        let [_utterance, _channel, start, duration, word, ref rest @ ..] = fields[..] else {
+            return Err(format!("line {}: expected at least 5 fields", i + 1));
+        };
+
+        let seconds = |s: &str| {
+            s.parse::<f64>()
+                .map_err(|_| format!("line {}: invalid time {s:?}", i + 1))
+        };
+        let (start, duration) = (seconds(start)?, seconds(duration)?);
+        let confidence = rest
+            .first()
+            .map(|c| {
+                c.parse::<f32>()
+                    .map_err(|_| format!("line {}: invalid confidence {c:?}", i + 1))
+            })
+            .transpose()?;
+
+        timings.push(
+            Timing::new(
+                ms_from_seconds(start),
+                ms_from_seconds(start + duration),
+                format!(" {word}"),
+            )
+            .with_confidence(confidence),
+        );
+    }
+
+    Ok(timings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse;
+
+    #[test]
+    fn reads_one_timing_per_word() {
+        let timings =
+            parse(";; comment\nutt1 A 1.00 0.25 hello 0.9\nutt1 A 1.25 0.5 world\n").unwrap();
+        assert_eq!(timings.len(), 2);
+        assert_eq!((timings[0].start(), timings[0].end()), (1000, 1250));
+        assert_eq!(timings[0].text(), " hello");
+        assert_eq!(timings[0].confidence(), Some(0.9));
+        assert_eq!((timings[1].start(), timings[1].end()), (1250, 1750));
+        assert_eq!(timings[1].confidence(), None);
+    }
+
+    #[test]
+    fn reports_the_line_of_a_malformed_record() {
+        assert_eq!(
+            parse("utt1 A 1.00 0.25 hi\nutt1 A 1.00 0.25\n").unwrap_err(),
+            "line 2: expected at least 5 fields"
+        );
+        assert_eq!(
+            parse("utt1 A soon 0.25 hello\n").unwrap_err(),
+            "line 1: invalid time \"soon\""
+        );
+        assert_eq!(
+            parse("utt1 A 1.00 0.25 hello sure\n").unwrap_err(),
+            "line 1: invalid confidence \"sure\""
+        );
+    }
+}
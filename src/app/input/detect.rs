@@ -0,0 +1,166 @@
+use super::{CsvHandling, Format};
+use crate::transcribe::parse_clock_value;
+
+/// How much of the input is examined when detecting its format.
+pub const SNIFF_LEN: usize = 8 * 1024;
+
+/// Guesses the format of an input from its first few kilobytes.
+///
+/// The checks run from the most to the least distinctive signature, so that loosely structured
+/// formats like plain timestamped text don't claim files that match something stricter.
+pub fn sniff(prefix: &str) -> Option<Format> {
+    let prefix = prefix.trim_start_matches('\u{feff}').trim_start();
+    let mut lines = prefix.lines().map(str::trim).filter(|l| !l.is_empty());
+    let first = lines.next()?;
+
+    if first.starts_with("WEBVTT") {
+        return Some(Format::Vtt);
+    }
+
+    if first.starts_with('<') {
+        return if prefix.contains("<ANNOTATION_DOCUMENT") {
+            Some(Format::Eaf)
+        } else if prefix.contains("<tt") {
+            Some(Format::Ttml)
+        } else {
+            None
+        };
+    }
+
+    if first.starts_with('{') || prefix.starts_with("[{") || first == "[" {
+        return Some(sniff_json(prefix));
+    }
+
+    if first.chars().all(|c| c.is_ascii_digit()) && lines.next().is_some_and(|l| l.contains("-->"))
+    {
+        return Some(Format::Srt);
+    }
+
+    if is_lrc(prefix) {
+        return Some(Format::Lrc);
+    }
+
+    let header = first.to_lowercase();
+    if header.starts_with("start\t") {
+        return Some(Format::Tsv);
+    }
+    if header.starts_with("start,") {
+        return Some(Format::Csv(Some(CsvHandling::WhisperCppFix)));
+    }
+
+    let fields = first.split('\t').collect::<Vec<_>>();
+    if fields.len() >= 2 && fields[..2].iter().all(|f| f.trim().parse::<f64>().is_ok()) {
+        return Some(Format::Audacity);
+    }
+
+    let fields = first.split_whitespace().collect::<Vec<_>>();
+    if fields.len() >= 5 && fields[2..4].iter().all(|f| f.parse::<f64>().is_ok()) {
+        return Some(Format::Ctm);
+    }
+
+    if prefix
+        .lines()
+        .any(|l| super::timestamped::parse(l).is_ok_and(|t| !t.is_empty()))
+    {
+        return Some(Format::Timestamped);
+    }
+
+    None
+}
+
+/// Tells the JSON formats apart by their distinguishing top-level keys.
+fn sniff_json(prefix: &str) -> Format {
+    let has_key = |key: &str| prefix.contains(&format!("\"{key}\""));
+
+    if has_key("recognizedPhrases") {
+        Format::Azure
+    } else if has_key("results") && has_key("alternatives") {
+        Format::Gcp
+    } else if has_key("transcription") {
+        Format::WhisperCppJson
+    } else if has_key("segments") && has_key("speaker") {
+        Format::WhisperX
+    } else if has_key("segments") {
+        Format::WhisperVerboseJson
+    } else {
+        Format::Json
+    }
+}
+
+/// LRC files open with ID tags like `[ar:...]` or with `[mm:ss.xx]` line timestamps.
+fn is_lrc(prefix: &str) -> bool {
+    prefix.lines().map(str::trim).any(|line| {
+        let Some((tag, _)) = line.strip_prefix('[').and_then(|l| l.split_once(']')) else {
+            return false;
+        };
+        match tag.split_once(':') {
+            Some((key, _)) if ["ti", "ar", "al", "by", "length", "offset"].contains(&key) => true,
+            // Timestamped notes use whole seconds, LRC uses hundredths
+            Some(_) => tag.contains('.') && parse_clock_value(tag).is_some(),
+            None => false,
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{sniff, Format};
+
+    #[test]
+    fn recognizes_text_signatures() {
+        assert!(matches!(sniff("\u{feff}WEBVTT\n"), Some(Format::Vtt)));
+        assert!(matches!(
+            sniff("1\n00:00:01,000 --> 00:00:02,000\nHi\n"),
+            Some(Format::Srt)
+        ));
+        assert!(matches!(sniff("<tt xmlns=\"\">"), Some(Format::Ttml)));
+        assert!(matches!(
+            sniff("<?xml version=\"1.0\"?>\n<ANNOTATION_DOCUMENT>"),
+            Some(Format::Eaf)
+        ));
+        assert!(matches!(
+            sniff("[ar:Someone]\n[00:01.00]Hi"),
+            Some(Format::Lrc)
+        ));
+        assert!(matches!(sniff("start,end,text\n"), Some(Format::Csv(_))));
+        assert!(matches!(sniff("start\tend\ttext\n"), Some(Format::Tsv)));
+        assert!(matches!(sniff("0.5\t1.5\tHi\n"), Some(Format::Audacity)));
+        assert!(matches!(sniff("utt A 0.5 0.2 hi\n"), Some(Format::Ctm)));
+        assert!(matches!(
+            sniff("[00:01:23] Hi\n"),
+            Some(Format::Timestamped)
+        ));
+    }
+
+    #[test]
+    fn tells_json_formats_apart() {
+        assert!(matches!(
+            sniff("{\"recognizedPhrases\": []}"),
+            Some(Format::Azure)
+        ));
+        assert!(matches!(
+            sniff("{\"results\": [{\"alternatives\": []}]}"),
+            Some(Format::Gcp)
+        ));
+        assert!(matches!(
+            sniff("{\"transcription\": []}"),
+            Some(Format::WhisperCppJson)
+        ));
+        assert!(matches!(
+            sniff("{\"segments\": [{\"speaker\": \"A\"}]}"),
+            Some(Format::WhisperX)
+        ));
+        assert!(matches!(
+            sniff("{\"segments\": []}"),
+            Some(Format::WhisperVerboseJson)
+        ));
+        assert!(matches!(sniff("[{\"start\": 0}]"), Some(Format::Json)));
+    }
+
+    #[test]
+    fn gives_up_on_unrecognized_input() {
+        assert!(sniff("").is_none());
+        assert!(sniff("just some notes\n").is_none());
+        assert!(sniff("<html>").is_none());
+    }
+}
@@ -0,0 +1,208 @@
+use csv::StringRecord;
+
+/// The fields a `Timing` is deserialized from, in their default column order.
+const FIELDS: [&str; 3] = ["start", "end", "text"];
+
+/// How CSV and TSV input is laid out, for files that weren't written by whisper.cpp.
+#[derive(clap::Args, Debug, Clone, Default)]
+pub struct CsvDialect {
+    /// The field delimiter, a single ASCII character or "tab".
+    #[arg(long, value_parser = parse_delimiter)]
+    delimiter: Option<u8>,
+
+    /// Treat quote characters as ordinary text.
+    #[arg(long)]
+    no_quoting: bool,
+
+    /// The input has no header row; columns are then start, end and text, in that order, unless
+    /// mapped by index.
+    #[arg(long)]
+    no_header: bool,
+
+    /// The column holding start times, by header name or 1-based index.
+    #[arg(long, value_parser = Column::parse)]
+    start_column: Option<Column>,
+
+    /// The column holding end times, by header name or 1-based index.
+    #[arg(long, value_parser = Column::parse)]
+    end_column: Option<Column>,
+
+    /// The column holding the text, by header name or 1-based index.
+    #[arg(long, value_parser = Column::parse)]
+    text_column: Option<Column>,
+
+    /// The column holding the speaker, by header name or 1-based index. A column named
+    /// `speaker` is read without this.
+    #[arg(long, value_parser = Column::parse)]
+    speaker_column: Option<Column>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Column {
+    Name(String),
+    /// Zero-based, converted from the 1-based index given on the command line.
+    Index(usize),
+}
+
+impl Column {
+    fn parse(s: &str) -> Result<Self, String> {
+        match s.parse::<usize>() {
+            Ok(0) => Err("column indexes start at 1".to_string()),
+            Ok(i) => Ok(Self::Index(i - 1)),
+            Err(_) if s.is_empty() => Err("empty column name".to_string()),
+            Err(_) => Ok(Self::Name(s.to_string())),
+        }
+    }
+
+    fn position(&self, header: Option<&StringRecord>) -> Result<usize, String> {
+        match (self, header) {
+            (Self::Index(i), _) => Ok(*i),
+            (Self::Name(name), Some(header)) => header
+                .iter()
+                .position(|h| h.trim() == name)
+                .ok_or_else(|| format!("no column named {name:?}")),
+            (Self::Name(name), None) => Err(format!(
+                "column {name:?} can't be found by name without a header row"
+            )),
+        }
+    }
+}
+
+fn parse_delimiter(s: &str) -> Result<u8, String> {
+    match s {
+        "tab" | "\\t" => Ok(b'\t'),
+        _ => match s.as_bytes() {
+            [b] if b.is_ascii() => Ok(*b),
+            _ => Err("expected a single ASCII character".to_string()),
+        },
+    }
+}
+
+impl CsvDialect {
+    pub fn configure(&self, builder: &mut csv::ReaderBuilder) {
+        if let Some(delimiter) = self.delimiter {
+            builder.delimiter(delimiter);
+        }
+        if self.no_quoting {
+            builder.quoting(false);
+        }
+        builder.has_headers(!self.no_header);
+    }
+
+    /// The header to deserialize a record of `width` fields with, naming the mapped columns after
+    /// the `Timing` fields they hold.
+    pub fn header(
+        &self,
+        file_header: Option<&StringRecord>,
+        width: usize,
+    ) -> Result<StringRecord, String> {
+        let mut names = match file_header {
+            Some(header) => header.iter().map(|h| h.trim().to_string()).collect(),
+            None => (0..width)
+                .map(|i| FIELDS.get(i).map_or_else(String::new, ToString::to_string))
+                .collect::<Vec<_>>(),
+        };
+
+        let mapping = [
+            &self.start_column,
+            &self.end_column,
+            &self.text_column,
+            &self.speaker_column,
+        ];
+        for (field, column) in FIELDS.iter().chain(&["speaker"]).zip(mapping) {
+            let Some(column) = column else {
+                continue;
+            };
+            let position = column.position(file_header)?;
+            if position >= names.len() {
+                return Err(format!("the {field} column is past the last column"));
+            }
+
+            // A column that already had this name would otherwise shadow the mapped one
+            for name in names.iter_mut().filter(|n| n == field) {
+                name.clear();
+            }
+            names[position] = (*field).to_string();
+        }
+
+        Ok(StringRecord::from(names))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use csv::StringRecord;
+
+    use super::{parse_delimiter, Column, CsvDialect};
+
+    #[test]
+    fn maps_columns_onto_timing_fields() {
+        let dialect = CsvDialect {
+            start_column: Some(Column::parse("from").unwrap()),
+            text_column: Some(Column::parse("3").unwrap()),
+            ..CsvDialect::default()
+        };
+        let file_header = StringRecord::from(vec!["from", "end", "said", "start"]);
+        let header = dialect.header(Some(&file_header), 4).unwrap();
+        assert_eq!(header, StringRecord::from(vec!["start", "end", "text", ""]));
+    }
+
+    #[test]
+    fn maps_a_speaker_column() {
+        let dialect = CsvDialect {
+            speaker_column: Some(Column::parse("4").unwrap()),
+            ..CsvDialect::default()
+        };
+        let header = dialect.header(None, 4).unwrap();
+        assert_eq!(
+            header,
+            StringRecord::from(vec!["start", "end", "text", "speaker"])
+        );
+    }
+
+    #[test]
+    fn names_columns_without_a_header() {
+        let header = CsvDialect::default().header(None, 4).unwrap();
+        assert_eq!(header, StringRecord::from(vec!["start", "end", "text", ""]));
+    }
+
+    #[test]
+    fn parses_delimiters() {
+        assert_eq!(parse_delimiter("tab"), Ok(b'\t'));
+        assert_eq!(parse_delimiter(";"), Ok(b';'));
+        assert_eq!(
+            parse_delimiter("ab"),
+            Err("expected a single ASCII character".to_string())
+        );
+        assert!(parse_delimiter("é").is_err());
+    }
+
+    #[test]
+    fn reports_bad_column_mappings() {
+        assert_eq!(Column::parse("0").unwrap_err(), "column indexes start at 1");
+        assert_eq!(Column::parse("").unwrap_err(), "empty column name");
+
+        let by_name = CsvDialect {
+            end_column: Some(Column::parse("until").unwrap()),
+            ..CsvDialect::default()
+        };
+        assert_eq!(
+            by_name.header(None, 3).unwrap_err(),
+            "column \"until\" can't be found by name without a header row"
+        );
+        let file_header = StringRecord::from(vec!["start", "end", "text"]);
+        assert_eq!(
+            by_name.header(Some(&file_header), 3).unwrap_err(),
+            "no column named \"until\""
+        );
+
+        let past_end = CsvDialect {
+            text_column: Some(Column::parse("9").unwrap()),
+            ..CsvDialect::default()
+        };
+        assert_eq!(
+            past_end.header(None, 3).unwrap_err(),
+            "the text column is past the last column"
+        );
+    }
+}
@@ -0,0 +1,65 @@
+use std::io;
+
+use encoding_rs_io::DecodeReaderBytesBuilder;
+
+/// The character encoding of the input, which is transcoded to UTF-8 before parsing.
+#[derive(clap::ValueEnum, Debug, Clone, Copy, Default)]
+pub enum Encoding {
+    /// UTF-8 or UTF-16, going by the byte order mark, otherwise UTF-8
+    #[default]
+    Auto,
+    #[value(name = "utf-8")]
+    Utf8,
+    #[value(name = "utf-16le")]
+    Utf16Le,
+    #[value(name = "utf-16be")]
+    Utf16Be,
+    /// Windows-1252, the superset of Latin-1 that most tools actually write
+    #[value(name = "latin-1", alias = "windows-1252")]
+    Latin1,
+}
+
+impl Encoding {
+    pub fn decode<'a, R: io::Read + 'a>(self, reader: R) -> Box<dyn io::Read + 'a> {
+        let encoding = match self {
+            Self::Auto => None,
+            Self::Utf8 => Some(encoding_rs::UTF_8),
+            Self::Utf16Le => Some(encoding_rs::UTF_16LE),
+            Self::Utf16Be => Some(encoding_rs::UTF_16BE),
+            Self::Latin1 => Some(encoding_rs::WINDOWS_1252),
+        };
+
+        Box::new(
+            DecodeReaderBytesBuilder::new()
+                .encoding(encoding)
+                .strip_bom(true)
+                .build(reader),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use super::Encoding;
+
+    fn decode(encoding: Encoding, input: &[u8]) -> String {
+        let mut out = String::new();
+        encoding.decode(input).read_to_string(&mut out).unwrap();
+        out
+    }
+
+    #[test]
+    fn transcodes_to_utf8() {
+        assert_eq!(decode(Encoding::Auto, b"\xff\xfeh\x00i\x00"), "hi");
+        assert_eq!(decode(Encoding::Auto, b"\xef\xbb\xbfcaf\xc3\xa9"), "café");
+        assert_eq!(decode(Encoding::Utf16Be, b"\x00h\x00i"), "hi");
+        assert_eq!(decode(Encoding::Latin1, b"caf\xe9"), "café");
+    }
+
+    #[test]
+    fn replaces_invalid_bytes_in_a_named_encoding() {
+        assert_eq!(decode(Encoding::Utf8, b"caf\xe9"), "caf\u{fffd}");
+    }
+}
@@ -0,0 +1,28 @@
+use std::{io, thread, time::Duration};
+
+/// How long to wait before checking a followed file for new data.
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Reads a file that another program is still writing, waiting for more data at the end
+/// instead of reporting end of file, like `tail -f`.
+pub struct Follow<R> {
+    inner: R,
+}
+
+impl<R: io::Read> Follow<R> {
+    pub fn new(inner: R) -> Self {
+        Self { inner }
+    }
+}
+
+impl<R: io::Read> io::Read for Follow<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            let n = self.inner.read(buf)?;
+            if n > 0 || buf.is_empty() {
+                return Ok(n);
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+}
@@ -0,0 +1,198 @@
+use crate::transcribe::{parse_clock_value, Timing};
+
+/// How long the last line lasts when there's no `[length:]` tag to end it.
+const LAST_LINE_MS: u32 = 5000;
+
+struct Line {
+    start: u32,
+    text: String,
+    /// Word start times and text, from the enhanced `<mm:ss.xx>` format.
+    words: Vec<(u32, String)>,
+}
+
+/// Parses LRC lyrics into one `Timing` per line.
+///
+/// Each line ends where the next one starts; the last line ends at the `[length:]` tag if there
+/// is one, or a few seconds after it starts otherwise. Lines with several timestamps are repeated at each of them, and enhanced LRC word
+/// timestamps are kept as word timings. Blank timed lines only serve to end the line before.
+pub fn parse(content: &str) -> Result<Vec<Timing>, String> {
+    let content = content.strip_prefix('\u{feff}').unwrap_or(content);
+    let mut lines = vec![];
+    let mut offset = 0i64;
+    let mut length = None;
+
+    for (i, raw) in content.lines().enumerate() {
+        let mut rest = raw.trim();
+        let mut starts = vec![];
+
+        while let Some(tag) = rest.strip_prefix('[') {
+            let Some(close) = tag.find(']') else {
+                return Err(format!("line {}: unclosed tag", i + 1));
+            };
+            let (tag, after) = (&tag[..close], &tag[close + 1..]);
+            rest = after.trim_start();
+
+            if let Some(ms) = parse_clock_value(tag) {
+                starts.push(ms);
+                continue;
+            }
+
+            match tag.split_once(':').map(|(k, v)| (k.trim(), v.trim())) {
+                Some(("offset", value)) => {
+                    offset = value
+                        .parse()
+                        .map_err(|_| format!("line {}: invalid offset {value:?}", i + 1))?;
+                }
+                Some(("length", value)) => length = parse_clock_value(value),
+                _ => {}
+            }
+        }
+
+        let (text, words) = parse_words(rest);
+        for start in starts {
+            lines.push(Line {
+                start,
+                text: text.clone(),
+                words: words.clone(),
+            });
+        }
+    }
+
+    lines.sort_by_key(|l| l.start);
+
+    // A positive offset makes lyrics appear sooner. The `[length:]` tag is the length of the
+    // audio, so it stays as is.
+    let shift = |ms: u32| u32::try_from((i64::from(ms) - offset).max(0)).unwrap_or(u32::MAX);
+    for line in &mut lines {
+        line.start = shift(line.start);
+        for (start, _) in &mut line.words {
+            *start = shift(*start);
+        }
+    }
+
+    let last_end = lines.last().map_or(0, |l| {
+        length.unwrap_or_else(|| l.start.saturating_add(LAST_LINE_MS))
+    });
+    let ends = lines
+        .iter()
+        .skip(1)
+        .map(|l| l.start)
+        .chain([last_end])
+        .collect::<Vec<_>>();
+
+    Ok(lines
+        .into_iter()
+        .zip(ends)
+        .filter(|(line, _)| !line.text.is_empty())
+        .map(|(line, end)| {
+            let end = end.max(line.start);
+            let word_ends = line
+                .words
+                .iter()
+                .skip(1)
+                .map(|(start, _)| *start)
+                .chain([end])
+                .collect::<Vec<_>>();
+            let words = line
+                .words
+                .into_iter()
+                .zip(word_ends)
+                .map(|((start, text), end)| Timing::new(start, end, format!(" {text}")))
+                .collect();
+
+            Timing::new(line.start, end, format!(" {}", line.text)).with_words(words)
+        })
+        .collect())
+}
+
+/// Splits enhanced LRC text (`<00:01.00> some <00:01.50> words`) into plain text and words.
+fn parse_words(text: &str) -> (String, Vec<(u32, String)>) {
+    let mut words = vec![];
+    let mut plain = vec![];
+    let mut rest = text;
+
+    while !rest.is_empty() {
+        let stamp = rest
+            .strip_prefix('<')
+            .and_then(|r| r.split_once('>'))
+            .and_then(|(stamp, after)| Some((parse_clock_value(stamp)?, after)));
+
+        let Some((start, after)) = stamp else {
+            // Untimed text up to the next timestamp
+            let end = rest
+                .char_indices()
+                .skip(1)
+                .find(|&(_, c)| c == '<')
+                .map_or(rest.len(), |(i, _)| i);
+            plain.push(rest[..end].trim());
+            rest = &rest[end..];
+            continue;
+        };
+
+        let end = after.find('<').unwrap_or(after.len());
+        let word = after[..end].trim();
+        if !word.is_empty() {
+            words.push((start, word.to_string()));
+            plain.push(word);
+        }
+        rest = &after[end..];
+    }
+
+    let plain = plain
+        .into_iter()
+        .filter(|w| !w.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ");
+    (plain, words)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse;
+
+    #[test]
+    fn reads_lines_ending_at_the_next() {
+        let content = "[ar:Someone]\n[length: 00:10.00]\n[00:01.00][00:05.00]Chorus\n\
+                       [00:03.00]<00:03.00> one <00:03.50> two\n[00:04.00]\n";
+        let timings = parse(content).unwrap();
+        assert_eq!(timings.len(), 3);
+        assert_eq!((timings[0].start(), timings[0].end()), (1000, 3000));
+        assert_eq!(timings[0].content(), "Chorus");
+        assert_eq!((timings[1].start(), timings[1].end()), (3000, 4000));
+        assert_eq!(timings[1].content(), "one two");
+        assert_eq!(timings[1].words().len(), 2);
+        assert_eq!(timings[1].words()[1].start(), 3500);
+        assert_eq!((timings[2].start(), timings[2].end()), (5000, 10_000));
+    }
+
+    #[test]
+    fn applies_the_offset() {
+        let timings = parse("[offset:+500]\n[00:01.00]Hi\n[00:02.00]\n").unwrap();
+        assert_eq!((timings[0].start(), timings[0].end()), (500, 1500));
+    }
+
+    #[test]
+    fn keeps_the_length_as_is_with_an_offset() {
+        let timings = parse("[offset:+500]\n[length:00:10.00]\n[00:01.00]Hi\n").unwrap();
+        assert_eq!((timings[0].start(), timings[0].end()), (500, 10_000));
+    }
+
+    #[test]
+    fn gives_the_last_line_a_duration_without_a_length() {
+        let timings = parse("[00:01.00]Hi\n[00:02.00]there\n").unwrap();
+        assert_eq!((timings[0].start(), timings[0].end()), (1000, 2000));
+        assert_eq!((timings[1].start(), timings[1].end()), (2000, 7000));
+    }
+
+    #[test]
+    fn reports_the_line_of_a_malformed_tag() {
+        assert_eq!(
+            parse("[00:00.50]Hi\n[00:01.00 Hi\n").unwrap_err(),
+            "line 2: unclosed tag"
+        );
+        assert_eq!(
+            parse("[offset:soon]\n[00:01.00]Hi\n").unwrap_err(),
+            "line 1: invalid offset \"soon\""
+        );
+    }
+}
@@ -0,0 +1,672 @@
+pub mod ctm;
+mod detect;
+mod dialect;
+mod encoding;
+mod follow;
+pub mod lrc;
+mod normalize;
+pub mod srt;
+pub mod timestamped;
+pub mod ttml;
+pub mod vtt;
+
+use std::{
+    io::{self, BufRead, IsTerminal, Read},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use itertools::Itertools;
+
+use super::{
+    transcribe::{IterDyn, IteratorExt, Timing},
+    vendor::{audacity, azure, elan, gcp, openai_whisper, whisper_cpp, whisperx, BadCsvReader},
+};
+
+type TxResult = Result<Timing, csv::Error>;
+
+#[derive(clap::Args)]
+pub struct Input {
+    #[arg(
+        short = 'i',
+        long = "input-format",
+        name = "input-format",
+        default_value = "csv-fix",
+        value_enum
+    )]
+    format: Format,
+
+    /// The file, directory or glob pattern to read. Use `-` for stdin, which is also read when
+    /// this is omitted and stdin isn't a terminal.
+    #[arg(value_parser = Source::parse)]
+    source: Option<Source>,
+
+    /// Character encoding of the input
+    #[arg(long, default_value = "auto", value_enum)]
+    encoding: encoding::Encoding,
+
+    /// Keeps reading the source file as it grows, like `tail -f`, for transcripts that are
+    /// still being written. Needs a streaming input format such as csv or jsonl.
+    #[arg(long, default_value = "false")]
+    follow: bool,
+
+    #[command(flatten)]
+    options: Options,
+}
+
+/// Settings that only apply to some input formats.
+#[derive(clap::Args, Debug, Clone, Default)]
+pub struct Options {
+    /// The ELAN tier to read annotations from. Defaults to the first time-aligned tier.
+    #[arg(long)]
+    tier: Option<String>,
+
+    /// Keep whisper.cpp tokens apart, rather than joining each token that doesn't start with
+    /// whitespace to the one before it. Only csv, csv-fix and whisper-json input is joined.
+    #[arg(long)]
+    no_join: bool,
+
+    /// The longest a joined whisper.cpp token may last; anything longer is cut short. 0s keeps
+    /// every token's own end time.
+    #[arg(long, default_value = "500ms", value_parser = ParseDuration)]
+    max_utterance: Duration,
+
+    #[command(flatten)]
+    csv: dialect::CsvDialect,
+}
+
+impl Input {
+    pub fn source(&self) -> Result<Box<dyn io::Read>, io::Error> {
+        let reader: Box<dyn io::Read> = match self.source {
+            None if io::stdin().is_terminal() => {
+                return Err(io::Error::other(
+                    "no input given; pass a source or pipe data to stdin",
+                ));
+            }
+            None | Some(Source::Stdin) => Box::new(io::stdin()),
+            Some(Source::File(ref path)) if self.follow => {
+                if !self.format.streams() {
+                    return Err(io::Error::other(
+                        "--follow needs a streaming input format: csv, csv-fix, tsv, json or jsonl",
+                    ));
+                }
+                Box::new(follow::Follow::new(std::fs::File::open(path)?))
+            }
+            Some(Source::File(ref path)) => Box::new(std::fs::File::open(path)?),
+            Some(Source::Batch(_)) => {
+                return Err(io::Error::other("a batch source has no single reader"));
+            }
+        };
+        Ok(self.encoding.decode(reader))
+    }
+
+    pub fn follow(&self) -> bool {
+        self.follow
+    }
+
+    /// Opens one file of a batch with the same decoding as the main source.
+    pub fn open(&self, path: &Path) -> Result<Box<dyn io::Read>, io::Error> {
+        Ok(self.encoding.decode(std::fs::File::open(path)?))
+    }
+
+    /// The files to process independently, when the source is a directory or glob pattern.
+    pub fn batch(&self) -> Option<&[PathBuf]> {
+        match self.source {
+            Some(Source::Batch(ref paths)) => Some(paths),
+            _ => None,
+        }
+    }
+
+    pub fn format(&self) -> &Format {
+        &self.format
+    }
+
+    pub fn options(&self) -> &Options {
+        &self.options
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum CsvHandling {
+    WhisperCppFix,
+}
+
+#[derive(Debug, Clone)]
+pub enum Format {
+    Audacity,
+    Auto,
+    Azure,
+    Csv(Option<CsvHandling>),
+    Ctm,
+    Eaf,
+    Gcp,
+    Json,
+    JsonLines,
+    Lrc,
+    Srt,
+    Timestamped,
+    Tsv,
+    Ttml,
+    Vtt,
+    WhisperCppJson,
+    WhisperVerboseJson,
+    WhisperX,
+}
+
+impl Default for Format {
+    fn default() -> Self {
+        Self::Csv(Some(CsvHandling::WhisperCppFix))
+    }
+}
+
+impl clap::ValueEnum for Format {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[
+            Self::Csv(Some(CsvHandling::WhisperCppFix)),
+            Self::Csv(None),
+            Self::Audacity,
+            Self::Auto,
+            Self::Azure,
+            Self::Ctm,
+            Self::Eaf,
+            Self::Gcp,
+            Self::Json,
+            Self::JsonLines,
+            Self::Lrc,
+            Self::Srt,
+            Self::Timestamped,
+            Self::Tsv,
+            Self::Ttml,
+            Self::Vtt,
+            Self::WhisperCppJson,
+            Self::WhisperVerboseJson,
+            Self::WhisperX,
+        ]
+    }
+
+    fn to_possible_value(&self) -> Option<clap::builder::PossibleValue> {
+        use clap::builder::PossibleValue;
+        match self {
+            Format::Csv(Some(CsvHandling::WhisperCppFix)) => Some(
+                PossibleValue::new("csv-fix").help("same as csv, plus whisper.cpp formatting fix"),
+            ),
+            Format::Csv(None) => Some(PossibleValue::new("csv")),
+            Format::Audacity => {
+                Some(PossibleValue::new("audacity").help("Audacity label track export"))
+            }
+            Format::Auto => {
+                Some(PossibleValue::new("auto").help("detect the format from the first few KB"))
+            }
+            Format::Azure => {
+                Some(PossibleValue::new("azure").help("Azure Speech batch transcription JSON"))
+            }
+            Format::Ctm => Some(PossibleValue::new("ctm").help("Kaldi/NeMo word-level CTM")),
+            Format::Eaf => Some(PossibleValue::new("eaf").help("ELAN annotations, see --tier")),
+            Format::Gcp => {
+                Some(PossibleValue::new("gcp").help("Google Cloud Speech-to-Text response JSON"))
+            }
+            Format::Json => Some(PossibleValue::new("json")),
+            Format::JsonLines => Some(PossibleValue::new("jsonl").help("one JSON record per line")),
+            Format::Lrc => Some(PossibleValue::new("lrc").help("lyrics, including enhanced LRC")),
+            Format::Srt => Some(PossibleValue::new("srt")),
+            Format::Timestamped => Some(
+                PossibleValue::new("timestamped").help("plain text lines like [00:01:23] text"),
+            ),
+            Format::Tsv => Some(PossibleValue::new("tsv")),
+            Format::Ttml => Some(PossibleValue::new("ttml").help("TTML or DFXP captions")),
+            Format::Vtt => Some(PossibleValue::new("vtt")),
+            Format::WhisperCppJson => {
+                Some(PossibleValue::new("whisper-json").help("whisper.cpp's -oj output"))
+            }
+            Format::WhisperVerboseJson => Some(
+                PossibleValue::new("whisper-verbose-json")
+                    .help("Whisper verbose_json, keeping word timings"),
+            ),
+            Format::WhisperX => Some(
+                PossibleValue::new("whisperx").help("WhisperX JSON, keeping speakers and words"),
+            ),
+        }
+    }
+}
+
+impl Format {
+    /// Whether records are parsed as they're read, rather than after reading all the input.
+    pub fn streams(&self) -> bool {
+        matches!(
+            self,
+            Self::Csv(_) | Self::Tsv | Self::Json | Self::JsonLines
+        )
+    }
+
+    /// Whether records may be whisper.cpp tokens, which are joined into words unless
+    /// `--no-join` is given.
+    fn has_tokens(&self) -> bool {
+        matches!(self, Self::Csv(_) | Self::WhisperCppJson)
+    }
+
+    /// Reads timings from `reader`. Formats read record by record end the timings at the first
+    /// record that can't be read, passing its error to `on_error`.
+    pub fn consume_reader<'a, R, E>(
+        &self,
+        reader: R,
+        options: &Options,
+        on_error: E,
+    ) -> io::Result<IterDyn<'a>>
+    where
+        R: io::Read + 'a,
+        E: FnMut(io::Error) + 'a,
+    {
+        if let Self::Auto = self {
+            let (format, reader) = Self::detect(reader)?;
+            return format.consume_reader(reader, options, on_error);
+        }
+
+        let timings = self.parse_reader(reader, options, on_error)?;
+        Ok(if self.has_tokens() && !options.no_join {
+            timings.join_continuations(Some(options.max_utterance).filter(|d| !d.is_zero()))
+        } else {
+            timings
+        })
+    }
+
+    /// Works out the format from the start of `reader`, returning it with a reader that still
+    /// yields all of the input.
+    fn detect<'a, R: io::Read + 'a>(mut reader: R) -> io::Result<(Self, Box<dyn io::Read + 'a>)> {
+        let mut prefix = vec![];
+        reader
+            .by_ref()
+            .take(detect::SNIFF_LEN as u64)
+            .read_to_end(&mut prefix)?;
+
+        let format = detect::sniff(&String::from_utf8_lossy(&prefix)).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                "unrecognized input format; pass --input-format explicitly",
+            )
+        })?;
+        // Boxed so that consume_reader isn't instantiated for ever-longer chains
+        Ok((format, Box::new(io::Cursor::new(prefix).chain(reader))))
+    }
+
+    fn parse_reader<'a, R, E>(
+        &self,
+        reader: R,
+        options: &Options,
+        on_error: E,
+    ) -> io::Result<IterDyn<'a>>
+    where
+        R: io::Read + 'a,
+        E: FnMut(io::Error) + 'a,
+    {
+        Ok(match self {
+            Self::Auto => unreachable!("the format is detected before parsing"),
+            Self::Csv(_) | Self::Tsv => {
+                let mut builder = csv::ReaderBuilder::new();
+                if let Self::Tsv = self {
+                    // Tab-separated output is rarely quoted, so quotes are kept as text
+                    builder.delimiter(b'\t').quoting(false);
+                }
+                options.csv.configure(&mut builder);
+
+                let reader = normalize::NormalizeLines::new(reader);
+                let mut csv_reader: csv::Reader<Box<dyn io::Read + 'a>> = match self {
+                    Self::Csv(Some(CsvHandling::WhisperCppFix)) => {
+                        BadCsvReader::new(reader).into_csv_reader(builder)
+                    }
+                    _ => builder.from_reader(Box::new(reader)),
+                };
+
+                let file_header = csv_reader
+                    .has_headers()
+                    .then(|| csv_reader.headers().cloned())
+                    .transpose()
+                    .map_err(invalid_data)?;
+
+                // Records are read lazily so that followed files are processed as they grow
+                let dialect = options.csv.clone();
+                csv_reader
+                    .into_records()
+                    .map(move |r| {
+                        let record = r.map_err(invalid_data)?;
+                        let header =
+                            dialect
+                                .header(file_header.as_ref(), record.len())
+                                .map_err(|e| {
+                                    invalid_data(format!("invalid CSV column mapping: {e}"))
+                                })?;
+                        let timing: TxResult = record.deserialize(Some(&header));
+                        timing.map_err(invalid_data)
+                    })
+                    .map_while(until_error(on_error))
+                    .boxed()
+            }
+            Self::Eaf => read_whole(reader, "ELAN", |content| {
+                elan::parse(content, options.tier.as_deref())
+            })?,
+            Self::Gcp => read_whole(reader, "Google Cloud Speech JSON", gcp::parse)?,
+            Self::Json => {
+                let rdr = serde_json::Deserializer::from_reader(reader).into_iter::<Timing>();
+                rdr.map(|r| r.map_err(|e| invalid_data(format!("malformed JSON record: {e}"))))
+                    .map_while(until_error(on_error))
+                    .boxed()
+            }
+            Self::JsonLines => io::BufReader::new(reader)
+                .lines()
+                .zip(1..)
+                .filter_map(|(line, n)| match line {
+                    Ok(line) if line.trim().is_empty() => None,
+                    Ok(line) => Some(serde_json::from_str::<Timing>(&line).map_err(|e| {
+                        io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            format!("malformed JSON record on line {n}: {e}"),
+                        )
+                    })),
+                    Err(e) => Some(Err(io::Error::new(
+                        e.kind(),
+                        format!("unreadable JSON Lines input on line {n}: {e}"),
+                    ))),
+                })
+                .map_while(until_error(on_error))
+                .boxed(),
+            Self::Lrc => read_whole(reader, "LRC", lrc::parse)?,
+            Self::Srt => read_whole(reader, "SRT", srt::parse)?,
+            Self::Timestamped => read_whole(reader, "timestamped text", timestamped::parse)?,
+            Self::Ttml => read_whole(reader, "TTML", ttml::parse)?,
+            Self::Vtt => read_whole(reader, "WebVTT", vtt::parse)?,
+            Self::Audacity => read_whole(reader, "Audacity", audacity::parse)?,
+            Self::Azure => read_whole(reader, "Azure Speech JSON", azure::parse)?,
+            Self::Ctm => read_whole(reader, "CTM", ctm::parse)?,
+            Self::WhisperCppJson => read_whole(reader, "whisper.cpp JSON", whisper_cpp::parse)?,
+            Self::WhisperVerboseJson => read_whole(reader, "Whisper JSON", openai_whisper::parse)?,
+            Self::WhisperX => read_whole(reader, "WhisperX JSON", whisperx::parse)?,
+        })
+    }
+}
+
+fn invalid_data(e: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
+
+/// Passes on timings until the first error, which goes to `on_error`.
+fn until_error<T>(mut on_error: impl FnMut(io::Error)) -> impl FnMut(io::Result<T>) -> Option<T> {
+    move |result| result.map_err(&mut on_error).ok()
+}
+
+/// Reads all of `reader` before parsing it, for formats that can't be parsed incrementally.
+fn read_whole<'a, R, F>(mut reader: R, name: &str, parse: F) -> io::Result<IterDyn<'a>>
+where
+    R: io::Read,
+    F: FnOnce(&str) -> Result<Vec<Timing>, String>,
+{
+    let mut content = String::new();
+    reader.read_to_string(&mut content).map_err(|e| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("unreadable {name} input: {e}"),
+        )
+    })?;
+
+    let timings = parse(&content).map_err(|e| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("malformed {name} input: {e}"),
+        )
+    })?;
+    Ok(timings.into_iter().boxed())
+}
+
+#[derive(Debug, Clone)]
+pub enum Source {
+    Stdin,
+    File(String),
+    /// The files in a directory or matching a glob pattern, in sorted order.
+    Batch(Vec<PathBuf>),
+}
+
+impl Source {
+    fn parse(s: &str) -> Result<Self, String> {
+        let path = Path::new(s);
+        if s == "-" {
+            Ok(Self::Stdin)
+        } else if path.is_dir() {
+            let mut paths = std::fs::read_dir(path)
+                .map_err(|e| e.to_string())?
+                .map(|entry| entry.map(|e| e.path()))
+                .filter_ok(|p| {
+                    p.is_file()
+                        && !p
+                            .file_name()
+                            .is_some_and(|name| name.to_string_lossy().starts_with('.'))
+                })
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| e.to_string())?;
+            paths.sort();
+            Ok(Self::Batch(paths))
+        } else if s.contains(['*', '?', '[']) && !path.exists() {
+            let paths = glob::glob(s)
+                .map_err(|e| e.to_string())?
+                .filter_ok(|p| p.is_file())
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| e.to_string())?;
+            if paths.is_empty() {
+                return Err(format!("no files match {s:?}"));
+            }
+            Ok(Self::Batch(paths))
+        } else {
+            Ok(Self::File(s.to_string()))
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ParseDuration;
+
+impl clap::builder::TypedValueParser for ParseDuration {
+    type Value = Duration;
+
+    fn parse_ref(
+        &self,
+        _cmd: &clap::Command,
+        arg: Option<&clap::Arg>,
+        value: &std::ffi::OsStr,
+    ) -> Result<Self::Value, clap::Error> {
+        use clap::error::{ContextKind, ContextValue, ErrorKind};
+        let error = |kind: ErrorKind, msg: &str| -> clap::Error {
+            let attribution = arg.map(|arg| format!(" for option '{}'", arg.get_id()));
+            let mut e = clap::Error::new(kind);
+            e.insert(
+                ContextKind::Custom,
+                ContextValue::String(
+                    match attribution {
+                        Some(attribution) => format!("{msg}{attribution}"),
+                        None => msg.to_string(),
+                    }
+                    .clone(),
+                ),
+            );
+            e
+        };
+
+        let Some(s) = value.to_str() else {
+            return Err(error(
+                ErrorKind::MissingRequiredArgument,
+                "didn't receive a string",
+            ));
+        };
+
+        Self::parse(s).map_err(|msg| error(ErrorKind::ValueValidation, msg))
+    }
+}
+
+impl ParseDuration {
+    /// Parses a whole number of seconds or milliseconds, such as `2s` or `500ms`.
+    pub fn parse(s: &str) -> Result<Duration, &'static str> {
+        let digits = s
+            .chars()
+            .take_while(char::is_ascii_digit)
+            .collect::<String>();
+
+        if digits.is_empty() {
+            return Err("no digits found in value");
+        }
+
+        let rest = s.chars().skip(digits.len()).collect::<String>();
+        if rest.is_empty() {
+            return Err("no unit found in value");
+        }
+
+        let Ok(num) = digits.parse::<usize>() else {
+            return Err("couldn't parse digits");
+        };
+
+        match rest.as_str() {
+            "s" => Ok(Duration::from_secs(num as u64)),
+            "ms" => Ok(Duration::from_millis(num as u64)),
+            _ => Err("invalid duration unit; expected 's' or 'ms'"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use itertools::Itertools;
+
+    use super::{encoding::Encoding, Format, Options};
+    use crate::transcribe::Timing;
+
+    fn read_err(format: &Format, input: &'static [u8]) -> io::Error {
+        format
+            .consume_reader(input, &Options::default(), |_| {})
+            .err()
+            .expect("the input is rejected")
+    }
+
+    /// Reads every timing, with the error that ended them early if there was one.
+    fn read_all(
+        format: &Format,
+        input: impl io::Read + 'static,
+        options: &Options,
+    ) -> (Vec<Timing>, Option<io::Error>) {
+        let mut failure = None;
+        let timings = format
+            .consume_reader(input, options, |e| failure = Some(e))
+            .unwrap()
+            .collect_vec();
+        (timings, failure)
+    }
+
+    #[test]
+    fn ends_json_lines_at_a_malformed_record() {
+        let (timings, failure) = read_all(
+            &Format::JsonLines,
+            &b"{\"start\": 0, \"end\": 1000, \"text\": \" Hi\"}\n\n{\"start\": 1000}\n"[..],
+            &Options::default(),
+        );
+        assert_eq!(timings.len(), 1);
+        let err = failure.unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(
+            err.to_string()
+                .starts_with("malformed JSON record on line 3: missing field"),
+            "{err}"
+        );
+
+        let (timings, failure) = read_all(&Format::JsonLines, &b"\xff\n"[..], &Options::default());
+        assert!(timings.is_empty());
+        assert!(failure
+            .unwrap()
+            .to_string()
+            .starts_with("unreadable JSON Lines input on line 1: "));
+    }
+
+    #[test]
+    fn ends_csv_at_a_record_that_isnt_utf8() {
+        let input =
+            Encoding::Auto.decode(&b"start,end,text\n0,1000,caf\xc3\xa9\n1000,2000,caf\xe9\n"[..]);
+        let (timings, failure) = read_all(&Format::Csv(None), input, &Options::default());
+        assert_eq!(timings.len(), 1);
+        assert_eq!(timings[0].content(), "café");
+        let err = failure.unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().contains("invalid utf-8"), "{err}");
+
+        let err = read_err(&Format::Csv(None), b"start,end,t\xe9xt\n");
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().contains("invalid utf-8"), "{err}");
+    }
+
+    #[test]
+    fn ends_csv_at_a_column_mapping_that_doesnt_fit() {
+        #[derive(clap::Parser)]
+        struct Cli {
+            #[command(flatten)]
+            options: Options,
+        }
+
+        let Cli { options } = clap::Parser::parse_from(["sttx", "--text-column", "9"]);
+        let (timings, failure) = read_all(
+            &Format::Csv(None),
+            &b"start,end,text\n0,1000,Hi\n"[..],
+            &options,
+        );
+        assert!(timings.is_empty());
+        assert_eq!(
+            failure.unwrap().to_string(),
+            "invalid CSV column mapping: the text column is past the last column"
+        );
+    }
+
+    #[test]
+    fn ends_csv_and_json_at_a_malformed_record() {
+        let (timings, failure) = read_all(
+            &Format::Csv(None),
+            &b"start,end,text\n0,1000,Hi\nsoon,1,x\n"[..],
+            &Options::default(),
+        );
+        assert_eq!(timings.len(), 1);
+        assert!(failure
+            .unwrap()
+            .to_string()
+            .starts_with("CSV deserialize error: record 2"));
+
+        let (timings, failure) = read_all(
+            &Format::Json,
+            &b"{\"start\": 0, \"end\": 1000, \"text\": \" Hi\"} {\"start\": 1}"[..],
+            &Options::default(),
+        );
+        assert_eq!(timings.len(), 1);
+        assert!(failure
+            .unwrap()
+            .to_string()
+            .starts_with("malformed JSON record: missing field"));
+    }
+
+    #[test]
+    fn reports_malformed_and_unreadable_documents() {
+        let err = read_err(&Format::Vtt, b"00:01.000 --> 00:02.000\nHi\n");
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(
+            err.to_string(),
+            "malformed WebVTT input: missing WEBVTT header"
+        );
+
+        let err = read_err(&Format::Lrc, b"[00:01.00]caf\xe9\n");
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(
+            err.to_string().starts_with("unreadable LRC input: "),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn reports_an_unrecognized_format() {
+        let err = read_err(&Format::Auto, b"just some notes\n");
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(
+            err.to_string(),
+            "unrecognized input format; pass --input-format explicitly"
+        );
+    }
+}
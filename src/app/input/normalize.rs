@@ -0,0 +1,107 @@
+use std::io::{self, BufRead};
+
+const UTF8_BOM: &[u8] = b"\xef\xbb\xbf";
+
+/// Strips a leading UTF-8 byte order mark and turns CRLF and lone CR line endings into LF.
+///
+/// Windows tools commonly write both, and line-based readers like `BadCsvReader` only split on
+/// LF.
+pub struct NormalizeLines<R> {
+    inner: io::BufReader<R>,
+    at_start: bool,
+    after_cr: bool,
+}
+
+impl<R: io::Read> NormalizeLines<R> {
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner: io::BufReader::new(inner),
+            at_start: true,
+            after_cr: false,
+        }
+    }
+}
+
+impl<R: io::Read> io::Read for NormalizeLines<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        loop {
+            let available = self.inner.fill_buf()?;
+            if available.is_empty() {
+                return Ok(0);
+            }
+
+            if std::mem::take(&mut self.at_start) && available.starts_with(UTF8_BOM) {
+                self.inner.consume(UTF8_BOM.len());
+                continue;
+            }
+
+            let mut written = 0;
+            let mut consumed = 0;
+            for &byte in available {
+                if written == buf.len() {
+                    break;
+                }
+                consumed += 1;
+
+                match byte {
+                    b'\r' => {
+                        buf[written] = b'\n';
+                        written += 1;
+                    }
+                    // Already ended the line at the CR before it
+                    b'\n' if self.after_cr => {}
+                    _ => {
+                        buf[written] = byte;
+                        written += 1;
+                    }
+                }
+                self.after_cr = byte == b'\r';
+            }
+            self.inner.consume(consumed);
+
+            if written > 0 {
+                return Ok(written);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use super::NormalizeLines;
+
+    fn normalize(input: &[u8]) -> String {
+        let mut out = String::new();
+        NormalizeLines::new(input).read_to_string(&mut out).unwrap();
+        out
+    }
+
+    #[test]
+    fn strips_the_bom_and_normalizes_line_endings() {
+        assert_eq!(normalize(b"\xef\xbb\xbfa\r\nb\rc\n"), "a\nb\nc\n");
+        assert_eq!(normalize(b"a\r\n\r\nb"), "a\n\nb");
+    }
+
+    #[test]
+    fn keeps_state_across_short_reads() {
+        let mut reader = NormalizeLines::new(&b"a\r\nb"[..]);
+        let mut out = vec![];
+        let mut byte = [0];
+        while reader.read(&mut byte).unwrap() > 0 {
+            out.push(byte[0]);
+        }
+        assert_eq!(out, b"a\nb");
+    }
+
+    #[test]
+    fn leaves_other_input_alone() {
+        assert_eq!(normalize(b""), "");
+        assert_eq!(normalize(b"a\xef\xbb\xbf\n"), "a\u{feff}\n");
+    }
+}
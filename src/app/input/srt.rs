@@ -0,0 +1,78 @@
+use crate::transcribe::{parse_clock_value, Timing};
+
+/// Parses the `start --> end` line of a cue, ignoring anything after the end timestamp (such as
+/// `WebVTT` cue settings).
+pub fn parse_cue_timing(line: &str) -> Option<(u32, u32)> {
+    let (start, rest) = line.split_once("-->")?;
+    let end = rest.split_whitespace().next()?;
+    Some((parse_clock_value(start)?, parse_clock_value(end)?))
+}
+
+/// Parses `SubRip` (`.srt`) content into one `Timing` per cue.
+///
+/// Cue indices are optional, and multi-line cue text is kept with its line breaks.
+pub fn parse(content: &str) -> Result<Vec<Timing>, String> {
+    let content = content.strip_prefix('\u{feff}').unwrap_or(content);
+    let mut lines = content.lines().enumerate().peekable();
+    let mut timings = vec![];
+
+    while let Some((i, line)) = lines.next() {
+        let line = line.trim();
+        if line.is_empty() || (line.chars().all(|c| c.is_ascii_digit()) && !line.contains("-->")) {
+            continue;
+        }
+
+        let Some((start, end)) = parse_cue_timing(line) else {
+            return Err(format!(
+                "line {}: expected a cue timing, found {line:?}",
+                i + 1
+            ));
+        };
+
+        let mut text = vec![];
+        while let Some((_, line)) = lines.next_if(|(_, l)| !l.trim().is_empty()) {
+            text.push(line.trim_end());
+        }
+
+        timings.push(Timing::new(start, end, format!(" {}", text.join("\n"))));
+    }
+
+    Ok(timings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse, parse_cue_timing};
+
+    #[test]
+    fn reads_cues_with_or_without_indices() {
+        let content = "\u{feff}1\n00:00:01,000 --> 00:00:02,500\nHello\nthere\n\n\
+                       00:00:03.000 --> 00:00:04.000\nBye\n";
+        let timings = parse(content).unwrap();
+        assert_eq!(timings.len(), 2);
+        assert_eq!((timings[0].start(), timings[0].end()), (1000, 2500));
+        assert_eq!(timings[0].content(), "Hello\nthere");
+        assert_eq!(timings[1].content(), "Bye");
+    }
+
+    #[test]
+    fn ignores_cue_settings() {
+        assert_eq!(
+            parse_cue_timing("00:01.000 --> 00:02.000 align:start"),
+            Some((1000, 2000))
+        );
+        assert_eq!(parse_cue_timing("00:01.000 -> 00:02.000"), None);
+    }
+
+    #[test]
+    fn reports_the_line_of_a_bad_timing() {
+        assert_eq!(
+            parse("1\n00:00:01,000 --> later\nHi\n").unwrap_err(),
+            "line 2: expected a cue timing, found \"00:00:01,000 --> later\""
+        );
+        assert_eq!(
+            parse("\nHi\n").unwrap_err(),
+            "line 2: expected a cue timing, found \"Hi\""
+        );
+    }
+}
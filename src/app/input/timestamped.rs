@@ -0,0 +1,179 @@
+use itertools::Itertools;
+
+use crate::transcribe::{parse_clock_value, Timing};
+
+/// Speaker headers are short; longer lines ending in a time are ordinary text.
+const MAX_SPEAKER_WORDS: usize = 4;
+
+/// How long the last segment is taken to last per word, since nothing follows to end it.
+const LAST_SEGMENT_MS_PER_WORD: u32 = 400;
+
+/// The shortest duration given to the last segment.
+const MIN_LAST_SEGMENT_MS: u32 = 1000;
+
+struct Segment {
+    start: u32,
+    speaker: Option<String>,
+    text: Vec<String>,
+}
+
+/// Parses plain text transcripts with inline timestamps, such as meeting notes.
+///
+/// A segment starts at each line beginning with a timestamp, like `[00:01:23] text`,
+/// `(1:23) text` or `00:01:23 --> text`, or at a speaker header ending in one, like
+/// `Jane Doe  1:23` in Otter exports or `Speaker 2 - (01:23)`. Untimed lines continue the
+/// segment before them, and each segment ends where the next one starts. A final timestamp on a
+/// line of its own ends the last segment; otherwise it's given a duration from its word count.
+pub fn parse(content: &str) -> Result<Vec<Timing>, String> {
+    let mut segments: Vec<Segment> = vec![];
+
+    for line in content.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        if let Some((start, text)) = leading_timestamp(line) {
+            segments.push(Segment {
+                start,
+                speaker: None,
+                text: vec![text.to_string()],
+            });
+        } else if let Some((speaker, start)) = speaker_header(line) {
+            segments.push(Segment {
+                start,
+                speaker: Some(speaker.to_string()),
+                text: vec![],
+            });
+        } else if let Some(segment) = segments.last_mut() {
+            segment.text.push(line.to_string());
+        }
+    }
+
+    if segments.is_empty() && !content.trim().is_empty() {
+        return Err("no timestamped lines found".to_string());
+    }
+
+    let ends = segments
+        .iter()
+        .skip(1)
+        .map(|s| s.start)
+        .chain(segments.last().map(|s| s.start + nominal_duration(&s.text)))
+        .collect::<Vec<_>>();
+
+    Ok(segments
+        .into_iter()
+        .zip(ends)
+        .map(|(segment, end)| {
+            let text = segment.text.iter().filter(|t| !t.is_empty()).join(" ");
+            Timing::new(segment.start, end.max(segment.start), format!(" {text}"))
+                .with_speaker(segment.speaker)
+        })
+        .filter(|t| !t.content().is_empty())
+        .collect())
+}
+
+fn nominal_duration(text: &[String]) -> u32 {
+    let words = text
+        .iter()
+        .map(|t| t.split_whitespace().count())
+        .sum::<usize>();
+    u32::try_from(words)
+        .unwrap_or(u32::MAX)
+        .saturating_mul(LAST_SEGMENT_MS_PER_WORD)
+        .max(MIN_LAST_SEGMENT_MS)
+}
+
+/// A clock value with at least minutes, so that numbers in the text aren't mistaken for times.
+fn clock(s: &str) -> Option<u32> {
+    s.contains(':').then(|| parse_clock_value(s)).flatten()
+}
+
+fn leading_timestamp(line: &str) -> Option<(u32, &str)> {
+    let (start, rest) = if let Some(tag) = line.strip_prefix('[') {
+        let (tag, rest) = tag.split_once(']')?;
+        (clock(tag)?, rest)
+    } else if let Some(tag) = line.strip_prefix('(') {
+        let (tag, rest) = tag.split_once(')')?;
+        (clock(tag)?, rest)
+    } else {
+        let (token, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        (clock(token)?, rest)
+    };
+
+    let rest = rest.trim_start();
+    let rest = ["-->", "-", "–", "|"]
+        .iter()
+        .find_map(|sep| rest.strip_prefix(sep))
+        .unwrap_or(rest);
+    Some((start, rest.trim()))
+}
+
+/// Reads a header made of a speaker's name followed by a timestamp, optionally set apart by a
+/// separator or brackets, like `Jane Doe  1:23`, `Speaker 2 - 01:23` or `JANE (1:23)`.
+fn speaker_header(line: &str) -> Option<(&str, u32)> {
+    let (speaker, time) = line.rsplit_once(char::is_whitespace)?;
+    let time = time
+        .strip_prefix(['(', '['])
+        .and_then(|t| t.strip_suffix([')', ']']))
+        .unwrap_or(time);
+    let speaker = speaker.trim_end();
+    let speaker = ["-", "–", "|", "·"]
+        .iter()
+        .find_map(|sep| speaker.strip_suffix(sep))
+        .unwrap_or(speaker)
+        .trim_end();
+    is_name(speaker).then_some((speaker, clock(time)?))
+}
+
+/// Whether `s` reads as a name: a few words, each capitalized or a number, like `Jane Doe`,
+/// `Dr. J. Smith` or `Speaker 2`.
+fn is_name(s: &str) -> bool {
+    let words = s.split_whitespace().collect::<Vec<_>>();
+    let starts_upper = |w: &str| w.chars().next().is_some_and(char::is_uppercase);
+    (1..=MAX_SPEAKER_WORDS).contains(&words.len())
+        && starts_upper(words[0])
+        && words.iter().all(|w| {
+            (starts_upper(w) || w.chars().all(|c| c.is_ascii_digit()))
+                && w.chars()
+                    .all(|c| c.is_alphanumeric() || matches!(c, '.' | '\'' | '-'))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse;
+
+    #[test]
+    fn reads_leading_timestamps() {
+        let timings = parse("[00:01] Hello\nthere\n(0:05) - Bye\n[00:09]").unwrap();
+        assert_eq!(timings.len(), 2);
+        assert_eq!((timings[0].start(), timings[0].end()), (1000, 5000));
+        assert_eq!(timings[0].content(), "Hello there");
+        assert_eq!((timings[1].start(), timings[1].end()), (5000, 9000));
+        assert_eq!(timings[1].content(), "Bye");
+    }
+
+    #[test]
+    fn reads_speaker_headers() {
+        let timings = parse("Jane Doe  0:01\nHi.\nSpeaker 2 - (0:04)\nHello.").unwrap();
+        assert_eq!(timings.len(), 2);
+        assert_eq!(timings[0].speaker(), Some("Jane Doe"));
+        assert_eq!(timings[1].speaker(), Some("Speaker 2"));
+        assert_eq!(timings[1].start(), 4000);
+    }
+
+    #[test]
+    fn keeps_sentences_ending_in_a_time_as_text() {
+        let timings = parse("[0:01] We met\nat 10:30").unwrap();
+        assert_eq!(timings.len(), 1);
+        assert_eq!(timings[0].content(), "We met at 10:30");
+    }
+
+    #[test]
+    fn gives_the_last_segment_a_duration() {
+        let timings = parse("[0:01] one two three four five").unwrap();
+        assert_eq!((timings[0].start(), timings[0].end()), (1000, 3000));
+    }
+
+    #[test]
+    fn rejects_text_without_timestamps() {
+        assert!(parse("just some notes").is_err());
+        assert_eq!(parse("").unwrap().len(), 0);
+    }
+}
@@ -0,0 +1,166 @@
+use crate::transcribe::{ms_from_seconds, parse_clock_value, Timing};
+
+/// Rates used to interpret frame and tick time expressions.
+struct Rates {
+    frame: f64,
+    tick: f64,
+}
+
+/// Parses TTML (including DFXP) into one `Timing` per `<p>` element.
+///
+/// `begin` and `end` (or `dur`) may be clock times or offsets such as `1.5s`, `40f` or `100t`,
+/// and are relative to the `begin` of any enclosing `<body>` or `<div>`. Line breaks are kept.
+pub fn parse(content: &str) -> Result<Vec<Timing>, String> {
+    let doc = roxmltree::Document::parse(content).map_err(|e| e.to_string())?;
+    let root = doc.root_element();
+
+    let attr = |node: roxmltree::Node, name: &str| {
+        node.attributes()
+            .find(|a| a.name() == name)
+            .map(|a| a.value().to_string())
+    };
+    let rate = |name: &str, default: f64| {
+        attr(root, name)
+            .and_then(|v| v.parse::<f64>().ok())
+            .unwrap_or(default)
+    };
+    let rates = Rates {
+        frame: rate("frameRate", 30.0),
+        tick: rate("tickRate", 1.0),
+    };
+
+    let mut timings = vec![];
+    for p in root.descendants().filter(|n| n.tag_name().name() == "p") {
+        let time = |name: &str| -> Result<Option<u32>, String> {
+            attr(p, name)
+                .map(|v| parse_time(&v, &rates).ok_or_else(|| format!("invalid time {v:?}")))
+                .transpose()
+        };
+
+        let Some(begin) = time("begin")? else {
+            continue;
+        };
+        let end = match (time("end")?, time("dur")?) {
+            (Some(end), _) => end,
+            (None, Some(dur)) => begin + dur,
+            (None, None) => continue,
+        };
+
+        let offset = p
+            .ancestors()
+            .skip(1)
+            .filter_map(|a| attr(a, "begin"))
+            .filter_map(|v| parse_time(&v, &rates))
+            .sum::<u32>();
+
+        timings.push(Timing::new(
+            offset + begin,
+            offset + end,
+            format!(" {}", text_of(p).trim()),
+        ));
+    }
+
+    Ok(timings)
+}
+
+fn text_of(node: roxmltree::Node) -> String {
+    node.children()
+        .map(|child| {
+            if child.is_text() {
+                // Source formatting whitespace collapses, as it would when rendered
+                child
+                    .text()
+                    .unwrap_or_default()
+                    .split_whitespace()
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            } else if child.tag_name().name() == "br" {
+                "\n".to_string()
+            } else {
+                text_of(child)
+            }
+        })
+        .fold(String::new(), |mut acc, part| {
+            let needs_space = !acc.is_empty()
+                && !acc.ends_with(['\n', ' '])
+                && !part.starts_with(['\n', ' ', ',', '.'])
+                && !part.is_empty();
+            if needs_space {
+                acc.push(' ');
+            }
+            acc.push_str(&part);
+            acc
+        })
+}
+
+fn parse_time(expr: &str, rates: &Rates) -> Option<u32> {
+    let expr = expr.trim();
+
+    // hh:mm:ss:ff
+    if expr.matches(':').count() == 3 {
+        let (clock, frames) = expr.rsplit_once(':')?;
+        let frames = frames.parse::<f64>().ok()?;
+        return Some(parse_clock_value(clock)? + ms_from_seconds(frames / rates.frame));
+    }
+
+    if expr.contains(':') {
+        return parse_clock_value(expr);
+    }
+
+    let split = expr.find(|c: char| c.is_ascii_alphabetic())?;
+    let (value, metric) = expr.split_at(split);
+    let value = value.parse::<f64>().ok()?;
+
+    let seconds = match metric {
+        "h" => value * 3600.0,
+        "m" => value * 60.0,
+        "s" => value,
+        "ms" => value / 1000.0,
+        "f" => value / rates.frame,
+        "t" => value / rates.tick,
+        _ => return None,
+    };
+    Some(ms_from_seconds(seconds))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse, parse_time, Rates};
+
+    #[test]
+    fn reads_paragraphs_relative_to_their_div() {
+        let content = r#"<tt xmlns="http://www.w3.org/ns/ttml"><body><div begin="10s">
+            <p begin="00:00:01.000" end="00:00:02.500">Hello<br/>there</p>
+            <p begin="3s" dur="500ms">Bye <span>now</span>.</p>
+            <p>Untimed</p>
+        </div></body></tt>"#;
+        let timings = parse(content).unwrap();
+        assert_eq!(timings.len(), 2);
+        assert_eq!((timings[0].start(), timings[0].end()), (11_000, 12_500));
+        assert_eq!(timings[0].content(), "Hello\nthere");
+        assert_eq!((timings[1].start(), timings[1].end()), (13_000, 13_500));
+        assert_eq!(timings[1].content(), "Bye now.");
+    }
+
+    #[test]
+    fn reads_frame_and_tick_times() {
+        let rates = Rates {
+            frame: 25.0,
+            tick: 10_000_000.0,
+        };
+        assert_eq!(parse_time("00:00:01:05", &rates), Some(1200));
+        assert_eq!(parse_time("50f", &rates), Some(2000));
+        assert_eq!(parse_time("15000000t", &rates), Some(1500));
+        assert_eq!(parse_time("1.5m", &rates), Some(90_000));
+        assert_eq!(parse_time("12 parsecs", &rates), None);
+    }
+
+    #[test]
+    fn reports_bad_times_and_markup() {
+        assert_eq!(
+            parse("<tt><body><p begin=\"soon\" end=\"2s\">Hi</p></body></tt>").unwrap_err(),
+            "invalid time \"soon\""
+        );
+        assert!(parse("<tt><body><p begin=\"1s\">unclosed</body></tt>").is_err());
+    }
+}
@@ -0,0 +1,140 @@
+use super::srt::parse_cue_timing;
+use crate::transcribe::Timing;
+
+/// Parses `WebVTT` (`.vtt`) content into one `Timing` per cue.
+///
+/// The header, `NOTE`, `STYLE` and `REGION` blocks, cue identifiers and cue settings are
+/// skipped, and markup such as `<v Speaker>`, `<c>` and inline timestamps is removed from the
+/// cue text. A voice tag opening the cue gives its speaker.
+pub fn parse(content: &str) -> Result<Vec<Timing>, String> {
+    let content = content.strip_prefix('\u{feff}').unwrap_or(content);
+    if !content.starts_with("WEBVTT") {
+        return Err("missing WEBVTT header".to_string());
+    }
+
+    let mut timings = vec![];
+    let mut lines = content.lines().enumerate().skip(1).peekable();
+
+    while let Some((i, line)) = lines.next() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        // Neither of these blocks can contain a blank line, so skipping to one is enough
+        let is_metadata = ["NOTE", "STYLE", "REGION"].iter().any(|kw| {
+            line == *kw
+                || line.starts_with(&format!("{kw} "))
+                || line.starts_with(&format!("{kw}\t"))
+        });
+        if is_metadata || (timings.is_empty() && !line.contains("-->") && is_header_setting(line)) {
+            while lines.next_if(|(_, l)| !l.trim().is_empty()).is_some() {}
+            continue;
+        }
+
+        // A line without an arrow is the cue's identifier
+        let (i, line) = if line.contains("-->") {
+            (i, line)
+        } else {
+            match lines.next() {
+                Some((i, next)) => (i, next.trim()),
+                None => break,
+            }
+        };
+
+        let Some((start, end)) = parse_cue_timing(line) else {
+            return Err(format!(
+                "line {}: expected a cue timing, found {line:?}",
+                i + 1
+            ));
+        };
+
+        let mut text = vec![];
+        let mut speaker = None;
+        while let Some((_, line)) = lines.next_if(|(_, l)| !l.trim().is_empty()) {
+            if text.is_empty() {
+                speaker = voice(line);
+            }
+            text.push(strip_markup(line.trim_end()));
+        }
+
+        timings
+            .push(Timing::new(start, end, format!(" {}", text.join("\n"))).with_speaker(speaker));
+    }
+
+    Ok(timings)
+}
+
+/// Header blocks may continue past the `WEBVTT` line with `key: value` metadata.
+fn is_header_setting(line: &str) -> bool {
+    line.split_once(':')
+        .is_some_and(|(key, _)| !key.is_empty() && !key.contains(char::is_whitespace))
+}
+
+/// The name in a voice tag such as `<v Speaker>` or `<v.loud Speaker>` opening `line`.
+fn voice(line: &str) -> Option<String> {
+    let tag = line.trim_start().strip_prefix("<v")?;
+    let tag = &tag[..tag.find('>')?];
+    // Classes are appended to the tag name with dots, and the name follows whitespace
+    let (classes, name) = tag.split_once(char::is_whitespace)?;
+    if !classes.is_empty() && !classes.starts_with('.') {
+        return None;
+    }
+    let name = unescape(name.trim());
+    (!name.is_empty()).then_some(name)
+}
+
+fn strip_markup(line: &str) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut rest = line;
+    while let Some(open) = rest.find('<') {
+        out.push_str(&rest[..open]);
+        let Some(close) = rest[open..].find('>') else {
+            rest = &rest[open..];
+            break;
+        };
+        rest = &rest[open + close + 1..];
+    }
+    out.push_str(rest);
+    unescape(&out)
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&nbsp;", "\u{a0}")
+        .replace("&lrm;", "\u{200e}")
+        .replace("&rlm;", "\u{200f}")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse;
+
+    #[test]
+    fn reads_cues_and_skips_metadata() {
+        let content = "WEBVTT\nKind: captions\n\nNOTE a comment\nstill the comment\n\n\
+                       intro\n00:01.000 --> 00:02.500 align:start\n<v.loud Jane>Hi &amp; \
+                       <c>welcome</c>\nback\n\n00:03.000 --> 00:04.000\n<00:03.500>Bye\n";
+        let timings = parse(content).unwrap();
+        assert_eq!(timings.len(), 2);
+        assert_eq!((timings[0].start(), timings[0].end()), (1000, 2500));
+        assert_eq!(timings[0].content(), "Hi & welcome\nback");
+        assert_eq!(timings[0].speaker(), Some("Jane"));
+        assert_eq!(timings[1].content(), "Bye");
+        assert_eq!(timings[1].speaker(), None);
+    }
+
+    #[test]
+    fn reports_a_missing_header_and_bad_timings() {
+        assert_eq!(
+            parse("00:01.000 --> 00:02.000\nHi\n").unwrap_err(),
+            "missing WEBVTT header"
+        );
+        assert_eq!(
+            parse("WEBVTT\n\n1\n00:01.000 --> soon\nHi\n").unwrap_err(),
+            "line 4: expected a cue timing, found \"00:01.000 --> soon\""
+        );
+    }
+}
@@ -1,6 +1,23 @@
+pub(crate) mod anki;
+pub(crate) mod chapters;
 pub mod cmd;
+pub(crate) mod edl;
+pub(crate) mod explain;
+pub(crate) mod expr;
+pub(crate) mod fcpxml;
 pub(crate) mod input;
 pub(crate) mod output;
+pub(crate) mod pager;
+pub(crate) mod podcast;
+pub(crate) mod pretty;
+pub(crate) mod retime;
+pub(crate) mod stage;
+pub(crate) mod template;
+pub(crate) mod text;
+pub(crate) mod timecode;
+pub(crate) mod tokens;
+pub(crate) mod verify;
+pub(crate) mod waveform;
 
 use clap::Parser;
 
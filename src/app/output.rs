@@ -1,47 +1,520 @@
-use clap::{builder::PossibleValue, Args, ValueEnum};
+use std::{
+    io::{self, IsTerminal, Write},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use clap::{
+    builder::{PossibleValue, PossibleValuesParser, TypedValueParser},
+    Args, ValueEnum,
+};
+
+use crate::transcribe::{ClockScale, SrtOptions, TimeUnit};
+
+use super::{
+    input::ParseDuration,
+    pager::Pager,
+    pretty::{Color, Style},
+    template::Template,
+    text::{bidi::RtlMarks, sanitize::Sanitize, wrap::Wrap},
+    timecode::FrameRate,
+};
 
 #[derive(Args)]
 pub struct Output {
-    #[arg(short = 'f', long = "format", default_value = "pretty", value_enum)]
-    format: Format,
+    /// Defaults to the format matching the extension of the output file, if any, and
+    /// otherwise to pretty.
+    #[arg(short = 'f', long = "format", value_enum)]
+    format: Option<Format>,
+
+    /// The path to which the program should write the output. Use `-` for stdout. May be given
+    /// more than once to write several files, each in the format matching its extension.
+    #[arg(short = 'o', long = "output", value_name = "SINK", default_value = "-", value_parser = Sink::parse)]
+    sinks: Vec<Sink>,
+
+    /// Compresses the output. Defaults to the compression matching a `.gz` or `.zst` extension
+    /// of the output file, if any.
+    #[arg(long, value_enum)]
+    compress: Option<Compression>,
+
+    #[command(flatten)]
+    mode: WriteMode,
+
+    /// Where each file's output is written when the source is a directory or glob pattern.
+    /// `{dir}`, `{stem}`, `{name}` and `{ext}` stand for the source file's directory, its name
+    /// without extension, its full name, and the output format's extension.
+    #[arg(long = "output-template", default_value = "{dir}/{stem}.{ext}")]
+    template: String,
+
+    #[command(flatten)]
+    json: JsonArgs,
+
+    /// The unit of `start` and `end` in JSON and CSV output: whole milliseconds (`ms`) or
+    /// fractional seconds (`s`).
+    #[arg(
+        long,
+        default_value = "ms",
+        value_parser = PossibleValuesParser::new(["ms", "s"]).map(|unit| match unit.as_str() {
+            "s" => TimeUnit::Seconds,
+            _ => TimeUnit::Milliseconds,
+        })
+    )]
+    time_unit: TimeUnit,
+
+    /// Colors `pretty` output, dimming the times so the text stands out.
+    #[arg(long, value_enum, default_value = "auto")]
+    color: Color,
+
+    /// The smallest clock unit shown for times in `pretty` output, e.g. `hours` to always
+    /// include the hour.
+    #[arg(
+        long,
+        value_parser = PossibleValuesParser::new(["seconds", "minutes", "hours"]).map(|scale| {
+            match scale.as_str() {
+                "seconds" => ClockScale::Seconds,
+                "minutes" => ClockScale::Minutes,
+                _ => ClockScale::Hours,
+            }
+        })
+    )]
+    clock_scale: Option<ClockScale>,
+
+    /// Shows `pretty` output written to a terminal in a pager: the given command, or else
+    /// `$PAGER` or `less`.
+    #[arg(
+        long,
+        value_name = "CMD",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "${PAGER:-less}"
+    )]
+    pager: Option<String>,
+
+    /// Marks silences between segments in `pretty` output, when they last at least the given
+    /// duration, e.g. `--show-gaps=500ms`. Defaults to 1s.
+    #[arg(
+        long,
+        value_name = "DURATION",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "1s",
+        value_parser = ParseDuration
+    )]
+    show_gaps: Option<Duration>,
+
+    /// Cleans up characters the output format may not support. Defaults to a level suited to
+    /// the chosen format.
+    #[arg(long, value_enum)]
+    sanitize: Option<Sanitize>,
+
+    /// Treats every cue as right-to-left when writing subtitles, instead of detecting it.
+    #[arg(long, default_value = "false")]
+    rtl: bool,
 
-    /// The path to which the program should write the output. Use `-` for stdout.
-    #[arg(short = 'o',  long = "output", default_value = "-", value_parser = Sink::parse)]
-    sink: Sink,
+    /// Inserts directional marks into right-to-left subtitle lines so punctuation renders on
+    /// the correct side. Defaults to `rlm` when `--rtl` is given.
+    #[arg(long, value_enum)]
+    rtl_marks: Option<RtlMarks>,
+
+    #[command(flatten)]
+    srt: SrtArgs,
+
+    /// Breaks the text of every cue into lines of at most WIDTH characters, using up to LINES
+    /// lines of balanced length, e.g. `42x2`. LINES defaults to 2, so `--max-line-length 42`
+    /// breaks cues longer than 42 characters into two balanced lines.
+    #[arg(
+        long,
+        visible_alias = "max-line-length",
+        value_name = "WIDTH[xLINES]",
+        value_parser = Wrap::parse
+    )]
+    wrap: Option<Wrap>,
+
+    /// The video frame rate, e.g. 25, 29.97 or 30000/1001. When given, cue boundaries are snapped
+    /// to frame edges and times are written as SMPTE timecode by `pretty` and `template` output.
+    /// `--verify` reports the snapped boundaries. Frame-based formats such as `edl` and `fcpxml`
+    /// default to 25.
+    #[arg(long, value_parser = FrameRate::parse)]
+    fps: Option<FrameRate>,
+
+    /// The line written for each segment by the `template` format, e.g.
+    /// `{start_ms}\t{end_hms}\t{text}`. Placeholders are `{start}`, `{end}` and `{duration}`
+    /// (add `_ms`, `_s` or `_hms` for other units, or `_tc` for SMPTE timecode at --fps),
+    /// `{index}`, `{text}`, `{words}` and `{speaker}`.
+    #[arg(
+        long = "template",
+        value_name = "TEMPLATE",
+        value_parser = Template::parse,
+        required_if_eq("format", "template")
+    )]
+    line_template: Option<Template>,
+
+    /// The most words to take from each segment's first sentence for `chapters` titles.
+    #[arg(long, default_value = "8", value_name = "N")]
+    chapter_title_words: usize,
+
+    /// Names the audio clip of each `anki` note, adding a sound field. `{index}`, `{start}` and
+    /// `{end}` stand for the note number and its times in milliseconds.
+    #[arg(long, value_name = "TEMPLATE")]
+    anki_audio: Option<String>,
+
+    /// Media file from which to compute waveform peaks for the `player` format. Requires
+    /// `ffmpeg` on the PATH.
+    #[arg(long, value_name = "FILE")]
+    media: Option<PathBuf>,
+
+    /// The number of waveform peaks to compute per second of media.
+    #[arg(long, default_value = "100", requires = "media")]
+    peaks_per_second: u32,
+
+    /// After writing, reads the output back and reports anything the format failed to preserve,
+    /// including text changed by sanitizing or wrapping.
+    #[arg(long, default_value = "false")]
+    verify: bool,
 }
 
 impl Output {
-    pub fn sink(&self) -> Result<Box<dyn std::io::Write>, std::io::Error> {
-        Ok(match self.sink {
-            Sink::Stdout => Box::new(std::io::stdout()),
-            Sink::File(ref path) => Box::new(std::fs::File::create(path)?),
+    pub fn sinks(&self) -> &[Sink] {
+        &self.sinks
+    }
+
+    /// The format to write to `sink` in.
+    pub fn format(&self, sink: &Sink) -> Format {
+        self.format.unwrap_or_else(|| match sink {
+            Sink::File(path) => Compression::strip_extension(Path::new(path))
+                .1
+                .extension()
+                .and_then(|ext| Format::from_extension(&ext.to_string_lossy()))
+                .unwrap_or(Format::Pretty { compact: false }),
+            Sink::Stdout => Format::Pretty { compact: false },
         })
     }
 
-    pub fn format(&self) -> &Format {
-        &self.format
+    /// Opens `sink` for writing, compressing what's written to it if called for.
+    pub fn open(&self, sink: &Sink) -> Result<Box<dyn Write>, io::Error> {
+        match sink {
+            Sink::Stdout => match &self.pager {
+                Some(cmd)
+                    if matches!(self.format(sink), Format::Pretty { .. })
+                        && self.compress.is_none()
+                        && io::stdout().is_terminal() =>
+                {
+                    Ok(Box::new(Pager::spawn(cmd)?))
+                }
+                _ => Compression::wrap(self.compress, Box::new(io::stdout())),
+            },
+            Sink::File(path) => {
+                let path = Path::new(path);
+                let compress = self
+                    .compress
+                    .or_else(|| Compression::strip_extension(path).0);
+                Compression::wrap(compress, self.create_file(path)?)
+            }
+        }
+    }
+
+    /// Creates the file at `path` for one file of a batch.
+    pub fn create(&self, path: &Path) -> Result<Box<dyn Write>, io::Error> {
+        Compression::wrap(self.compress, self.create_file(path)?)
+    }
+
+    fn create_file(&self, path: &Path) -> Result<Box<dyn Write>, io::Error> {
+        let file = if self.mode.append {
+            std::fs::OpenOptions::new()
+                .append(true)
+                .create(true)
+                .open(path)?
+        } else if self.mode.atomic {
+            std::fs::File::create(temp_path(path))?
+        } else {
+            std::fs::File::create(path)?
+        };
+        Ok(Box::new(file))
+    }
+
+    /// Moves an atomically written file into place once it's complete, or removes it if
+    /// writing failed. Does nothing for other write modes.
+    pub fn finish(&self, path: &Path, written: bool) -> Result<(), io::Error> {
+        if !self.mode.atomic {
+            return Ok(());
+        }
+        if written {
+            std::fs::rename(temp_path(path), path)
+        } else {
+            std::fs::remove_file(temp_path(path))
+        }
+    }
+
+    /// The format of each file written for a batch source.
+    pub fn batch_format(&self) -> Format {
+        self.format.unwrap_or(Format::Pretty { compact: false })
+    }
+
+    /// The path to write the output for one file of a batch to.
+    pub fn batch_target(&self, source: &Path) -> Result<PathBuf, std::io::Error> {
+        if self.sinks.iter().any(|sink| matches!(sink, Sink::File(_))) {
+            return Err(std::io::Error::other(
+                "batch sources write one file each; use --output-template instead of --output",
+            ));
+        }
+
+        let lossy =
+            |s: Option<&std::ffi::OsStr>| s.unwrap_or_default().to_string_lossy().into_owned();
+        let dir = match source.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir.to_string_lossy(),
+            _ => ".".into(),
+        };
+
+        let target = PathBuf::from(
+            self.template
+                .replace("{dir}", &dir)
+                .replace("{stem}", &lossy(source.file_stem()))
+                .replace("{name}", &lossy(source.file_name()))
+                .replace("{ext}", &self.batch_extension()),
+        );
+
+        if target == source {
+            return Err(std::io::Error::other(format!(
+                "output template would overwrite {}",
+                source.display()
+            )));
+        }
+        Ok(target)
+    }
+
+    fn batch_extension(&self) -> String {
+        let ext = self.batch_format().extension();
+        match self.compress {
+            Some(compress) => format!("{ext}.{}", compress.extension()),
+            None => ext.to_string(),
+        }
+    }
+
+    pub fn sanitize(&self, format: Format) -> Sanitize {
+        self.sanitize.unwrap_or_else(|| format.sanitize())
+    }
+
+    pub fn fps(&self) -> FrameRate {
+        self.fps.unwrap_or(FrameRate::PAL)
+    }
+
+    /// The layout of `pretty` output, colored if `to_terminal` and `--color` allows it.
+    pub fn pretty_style(&self, to_terminal: bool, compact: bool) -> Style {
+        Style {
+            compact,
+            color: self.color.enabled(to_terminal),
+            clock_scale: self.clock_scale,
+            show_gaps: self.show_gaps,
+            rate: self.fps,
+        }
+    }
+
+    /// The frame rate to snap cue boundaries to, if one was given.
+    pub fn frame_rate(&self) -> Option<FrameRate> {
+        self.fps
+    }
+
+    pub fn line_template(&self) -> Option<&Template> {
+        self.line_template.as_ref()
+    }
+
+    pub fn chapter_title_words(&self) -> usize {
+        self.chapter_title_words
+    }
+
+    pub fn anki_audio(&self) -> Option<&str> {
+        self.anki_audio.as_deref()
+    }
+
+    pub fn json_pretty(&self) -> bool {
+        self.json.pretty
+    }
+
+    pub fn token_counts(&self) -> bool {
+        self.json.token_counts
+    }
+
+    pub fn time_unit(&self) -> TimeUnit {
+        self.time_unit
+    }
+
+    pub fn verify(&self) -> bool {
+        self.verify
+    }
+
+    pub fn media(&self) -> Option<&PathBuf> {
+        self.media.as_ref()
+    }
+
+    pub fn peaks_per_second(&self) -> u32 {
+        self.peaks_per_second
+    }
+
+    pub fn srt_options(&self) -> SrtOptions {
+        SrtOptions {
+            crlf: self.srt.crlf,
+            bom: self.srt.bom,
+            first_index: self.srt.start_index,
+        }
+    }
+
+    pub fn wrap(&self) -> Option<Wrap> {
+        self.wrap
+    }
+
+    pub fn rtl(&self) -> bool {
+        self.rtl
+    }
+
+    pub fn rtl_marks(&self) -> Option<RtlMarks> {
+        self.rtl_marks.or_else(|| self.rtl.then_some(RtlMarks::Rlm))
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy)]
 pub enum Format {
+    Anki,
+    Chapters,
     Csv,
+    Edl,
+    Fcpxml,
+    Html,
     Json,
+    JsonLines,
+    Lrc { enhanced: bool },
     Srt,
-    Pretty,
+    Player,
+    Podcast,
+    Pretty { compact: bool },
+    Template,
+    Vtt,
+}
+
+impl Format {
+    /// The format usually meant by a file extension, for when none is given explicitly.
+    pub fn from_extension(ext: &str) -> Option<Self> {
+        match ext.to_ascii_lowercase().as_str() {
+            "csv" => Some(Self::Csv),
+            "edl" => Some(Self::Edl),
+            "fcpxml" => Some(Self::Fcpxml),
+            "htm" | "html" => Some(Self::Html),
+            "json" => Some(Self::Json),
+            "jsonl" | "ndjson" => Some(Self::JsonLines),
+            "lrc" => Some(Self::Lrc { enhanced: false }),
+            "srt" => Some(Self::Srt),
+            "txt" => Some(Self::Pretty { compact: false }),
+            "vtt" => Some(Self::Vtt),
+            _ => None,
+        }
+    }
+
+    /// Whether each cue is written as soon as it's ready, rather than once all are known.
+    pub fn streams(self) -> bool {
+        matches!(
+            self,
+            Self::Anki
+                | Self::Chapters
+                | Self::Csv
+                | Self::JsonLines
+                | Self::Lrc { .. }
+                | Self::Srt
+                | Self::Pretty { .. }
+                | Self::Template
+                | Self::Vtt
+        )
+    }
+
+    /// The file extension for this format, used to name batch outputs.
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Anki => "tsv",
+            Self::Chapters | Self::Pretty { .. } | Self::Template => "txt",
+            Self::Csv => "csv",
+            Self::Edl => "edl",
+            Self::Fcpxml => "fcpxml",
+            Self::Html => "html",
+            Self::Json | Self::Player | Self::Podcast => "json",
+            Self::JsonLines => "jsonl",
+            Self::Lrc { .. } => "lrc",
+            Self::Srt => "srt",
+            Self::Vtt => "vtt",
+        }
+    }
+
+    /// The character cleanup applied when `--sanitize` isn't given. Data formats keep the text
+    /// exactly as it was transcribed.
+    pub fn sanitize(self) -> Sanitize {
+        match self {
+            Self::Csv | Self::Json | Self::JsonLines | Self::Pretty { .. } => Sanitize::None,
+            _ => Sanitize::Control,
+        }
+    }
 }
 
 impl ValueEnum for Format {
     fn value_variants<'a>() -> &'a [Self] {
-        &[Self::Csv, Self::Json, Self::Srt, Self::Pretty]
+        &[
+            Self::Anki,
+            Self::Chapters,
+            Self::Csv,
+            Self::Edl,
+            Self::Fcpxml,
+            Self::Html,
+            Self::Json,
+            Self::JsonLines,
+            Self::Lrc { enhanced: false },
+            Self::Lrc { enhanced: true },
+            Self::Srt,
+            Self::Player,
+            Self::Podcast,
+            Self::Pretty { compact: false },
+            Self::Pretty { compact: true },
+            Self::Template,
+            Self::Vtt,
+        ]
     }
 
     fn to_possible_value(&self) -> Option<PossibleValue> {
         match self {
+            Self::Anki => {
+                Some(PossibleValue::new("anki").help("Anki notes to import, see --anki-audio"))
+            }
+            Self::Chapters => {
+                Some(PossibleValue::new("chapters").help("M:SS titles for video descriptions"))
+            }
             Self::Csv => Some(PossibleValue::new("csv")),
+            Self::Edl => {
+                Some(PossibleValue::new("edl").help("CMX 3600 edit decision list, see --fps"))
+            }
+            Self::Fcpxml => {
+                Some(PossibleValue::new("fcpxml").help("Final Cut Pro XML markers, see --fps"))
+            }
+            Self::Html => Some(
+                PossibleValue::new("html").help("a web page with a linkable anchor per segment"),
+            ),
             Self::Json => Some(PossibleValue::new("json")),
+            Self::JsonLines => Some(PossibleValue::new("jsonl").help("one JSON record per line")),
+            Self::Lrc { enhanced: false } => Some(PossibleValue::new("lrc")),
+            Self::Lrc { enhanced: true } => {
+                Some(PossibleValue::new("lrc-enhanced").help("LRC with <mm:ss.xx> word timings"))
+            }
             Self::Srt => Some(PossibleValue::new("srt")),
-            Self::Pretty => Some(PossibleValue::new("pretty")),
+            Self::Player => Some(
+                PossibleValue::new("player")
+                    .help("JSON cues for web transcript players, with waveform peaks from --media"),
+            ),
+            Self::Podcast => Some(
+                PossibleValue::new("podcast").help("Podcasting 2.0 transcript JSON, with speakers"),
+            ),
+            Self::Pretty { compact: false } => Some(PossibleValue::new("pretty")),
+            Self::Pretty { compact: true } => Some(
+                PossibleValue::new("pretty-compact")
+                    .help("one `[start–end] text` line per segment"),
+            ),
+            Self::Template => {
+                Some(PossibleValue::new("template").help("a line per segment, see --template"))
+            }
+            Self::Vtt => Some(PossibleValue::new("vtt").help("WebVTT, with <v> speaker tags")),
         }
     }
 }
@@ -61,4 +534,101 @@ impl Sink {
             Ok(Self::File(s.to_string()))
         }
     }
+
+    /// The file written to, if any.
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Self::Stdout => None,
+            Self::File(path) => Some(Path::new(path)),
+        }
+    }
+}
+
+/// Options for the `json` and `jsonl` formats.
+#[derive(Args)]
+struct JsonArgs {
+    /// Indents JSON output, which is otherwise written on a single line.
+    #[arg(long = "json-pretty", default_value = "false")]
+    pretty: bool,
+
+    /// Adds the number of tokens in each segment's text to JSON output, as counted by
+    /// --tokenizer.
+    #[arg(long, default_value = "false")]
+    token_counts: bool,
+}
+
+/// Options for the `srt` format.
+#[derive(Args)]
+struct SrtArgs {
+    /// Ends SRT lines with CRLF, as some Windows tools require.
+    #[arg(long = "srt-crlf", default_value = "false")]
+    crlf: bool,
+
+    /// Starts SRT output with a UTF-8 byte order mark.
+    #[arg(long = "srt-bom", default_value = "false")]
+    bom: bool,
+
+    /// The number given to the first SRT cue.
+    #[arg(long = "srt-start-index", default_value = "1", value_name = "N")]
+    start_index: usize,
+}
+
+/// How output files are written.
+#[derive(Args)]
+struct WriteMode {
+    /// Writes each output file under a temporary name and renames it into place once complete,
+    /// so that a failed run never leaves a partial file behind.
+    #[arg(long, default_value = "false", conflicts_with = "append")]
+    atomic: bool,
+
+    /// Appends to output files instead of replacing them. Best suited to formats written a
+    /// line at a time, such as `jsonl`.
+    #[arg(long, default_value = "false")]
+    append: bool,
+}
+
+/// The hidden file in the same directory as `path` that an atomic write goes to first.
+fn temp_path(path: &Path) -> PathBuf {
+    let mut name = std::ffi::OsString::from(".");
+    name.push(path.file_name().unwrap_or_default());
+    name.push(".tmp");
+    path.with_file_name(name)
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum Compression {
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    /// Splits a trailing `.gz` or `.zst` off the name of `path`, along with the compression it
+    /// stands for.
+    fn strip_extension(path: &Path) -> (Option<Self>, &Path) {
+        let compress = match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("gz") => Self::Gzip,
+            Some(ext) if ext.eq_ignore_ascii_case("zst") => Self::Zstd,
+            _ => return (None, path),
+        };
+        (Some(compress), path.file_stem().map_or(path, Path::new))
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Gzip => "gz",
+            Self::Zstd => "zst",
+        }
+    }
+
+    /// Wraps `w` in an encoder that finishes the compressed stream when dropped.
+    fn wrap(compress: Option<Self>, w: Box<dyn Write>) -> Result<Box<dyn Write>, io::Error> {
+        Ok(match compress {
+            Some(Self::Gzip) => Box::new(flate2::write::GzEncoder::new(
+                w,
+                flate2::Compression::default(),
+            )),
+            Some(Self::Zstd) => Box::new(zstd::Encoder::new(w, 0)?.auto_finish()),
+            None => w,
+        })
+    }
 }
@@ -0,0 +1,54 @@
+//! Paging long output through an external command such as `less`.
+use std::{
+    io::{self, Write},
+    process::{Child, ChildStdin, Command, Stdio},
+};
+
+/// A running pager that reads what's written to it.
+///
+/// The pager is waited on when this is dropped, so the program doesn't exit while the reader
+/// is still scrolling.
+pub struct Pager {
+    child: Child,
+    stdin: Option<ChildStdin>,
+}
+
+impl Pager {
+    /// Runs `cmd` through `sh -c`, so it may refer to variables such as `$PAGER`.
+    pub fn spawn(cmd: &str) -> io::Result<Self> {
+        let mut command = Command::new("sh");
+        command.arg("-c").arg(cmd).stdin(Stdio::piped());
+        // Keep colors, and skip paging when everything fits on one screen
+        if std::env::var_os("LESS").is_none() {
+            command.env("LESS", "FRX");
+        }
+
+        let mut child = command
+            .spawn()
+            .map_err(|e| io::Error::new(e.kind(), format!("couldn't run pager {cmd:?}: {e}")))?;
+        let stdin = child.stdin.take();
+        Ok(Self { child, stdin })
+    }
+
+    fn stdin(&mut self) -> &mut ChildStdin {
+        self.stdin.as_mut().expect("stdin is open until drop")
+    }
+}
+
+impl Write for Pager {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.stdin().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.stdin().flush()
+    }
+}
+
+impl Drop for Pager {
+    fn drop(&mut self) {
+        // Closing stdin tells the pager there's nothing more to show
+        drop(self.stdin.take());
+        let _ = self.child.wait();
+    }
+}
@@ -0,0 +1,51 @@
+//! Transcripts in the JSON format of the Podcast Namespace (Podcasting 2.0).
+use crate::transcribe::{ms_from_seconds, Timing};
+
+const VERSION: &str = "1.0.0";
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct Transcript {
+    version: String,
+    segments: Vec<Segment>,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Segment {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    speaker: Option<String>,
+    start_time: f64,
+    end_time: f64,
+    body: String,
+}
+
+impl FromIterator<Timing> for Transcript {
+    fn from_iter<I: IntoIterator<Item = Timing>>(iter: I) -> Self {
+        let seconds = |ms: u32| f64::from(ms) / 1000.0;
+        Self {
+            version: VERSION.to_string(),
+            segments: iter
+                .into_iter()
+                .map(|t| Segment {
+                    speaker: t.speaker().map(ToString::to_string),
+                    start_time: seconds(t.start()),
+                    end_time: seconds(t.end()),
+                    body: t.content().to_string(),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl Transcript {
+    pub fn into_timings(self) -> Vec<Timing> {
+        self.segments
+            .into_iter()
+            .map(|s| {
+                let start = ms_from_seconds(s.start_time);
+                let end = ms_from_seconds(s.end_time);
+                Timing::new(start, end, format!(" {}", s.body)).with_speaker(s.speaker)
+            })
+            .collect()
+    }
+}
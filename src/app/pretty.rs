@@ -0,0 +1,106 @@
+//! Human-readable output for reviewing transcripts in a terminal.
+use std::{io, time::Duration};
+
+use clap::ValueEnum;
+
+use super::timecode::FrameRate;
+use crate::transcribe::{format_clock_value, ClockScale, Timing};
+
+const DIM: &str = "\x1b[2m";
+const RESET: &str = "\x1b[0m";
+
+/// When to color pretty output.
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum Color {
+    /// When writing to a terminal, unless `NO_COLOR` is set.
+    Auto,
+    Always,
+    Never,
+}
+
+impl Color {
+    pub fn enabled(self, to_terminal: bool) -> bool {
+        match self {
+            Self::Auto => to_terminal && std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty()),
+            Self::Always => true,
+            Self::Never => false,
+        }
+    }
+}
+
+/// How pretty output is laid out.
+pub struct Style {
+    /// Writes one `[start–end] text` line per segment instead of a block.
+    pub compact: bool,
+    pub color: bool,
+    /// The smallest clock unit shown for start and end times.
+    pub clock_scale: Option<ClockScale>,
+    /// Marks silences between segments lasting at least this long.
+    pub show_gaps: Option<Duration>,
+    /// Writes times as SMPTE timecode at this rate instead of clock values.
+    pub rate: Option<FrameRate>,
+}
+
+impl Style {
+    fn dim(&self, s: &str) -> String {
+        if self.color {
+            format!("{DIM}{s}{RESET}")
+        } else {
+            s.to_string()
+        }
+    }
+
+    fn clock(&self, ms: u32) -> String {
+        match self.rate {
+            Some(rate) => rate.timecode(rate.frames(ms)).to_string(),
+            None => format_clock_value(ms, self.clock_scale),
+        }
+    }
+
+    /// The times of `t`, e.g. `0:01.00 - 0:02.50 (1.50)`.
+    fn header(&self, t: &Timing) -> String {
+        match self.rate {
+            Some(_) => format!("{} - {}", self.clock(t.start()), self.clock(t.end())),
+            None => format!(
+                "{} - {} ({})",
+                self.clock(t.start()),
+                self.clock(t.end()),
+                format_clock_value(t.duration(), Some(ClockScale::Seconds))
+            ),
+        }
+    }
+
+    /// Describes the silence from `end` to `start`, if it's long enough to show.
+    fn gap_marker(&self, end: u32, start: u32) -> Option<String> {
+        let min_gap = self.show_gaps?;
+        let gap = start.checked_sub(end).filter(|&gap| gap > 0)?;
+        (u128::from(gap) >= min_gap.as_millis())
+            .then(|| format!("(… {:.1}s silence …)", f64::from(gap) / 1000.0))
+    }
+
+    pub fn write<W: io::Write>(
+        &self,
+        timings: impl Iterator<Item = Timing>,
+        mut w: W,
+    ) -> io::Result<()> {
+        let mut previous_end = None;
+        for t in timings {
+            if let Some(marker) = previous_end.and_then(|end| self.gap_marker(end, t.start())) {
+                if self.compact {
+                    writeln!(w, "{}", self.dim(&marker))?;
+                } else {
+                    writeln!(w, "{}\n", self.dim(&marker))?;
+                }
+            }
+            previous_end = Some(t.end());
+
+            if self.compact {
+                let span = format!("[{}–{}]", self.clock(t.start()), self.clock(t.end()));
+                writeln!(w, "{} {}", self.dim(&span), t.content().replace('\n', " "))?;
+            } else {
+                writeln!(w, "{}\n{}\n", self.dim(&self.header(&t)), t.content())?;
+            }
+        }
+        Ok(())
+    }
+}
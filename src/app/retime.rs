@@ -0,0 +1,215 @@
+//! Corrections to the timing of a whole transcript, for syncing it to different media.
+use crate::transcribe::parse_clock_value;
+
+/// How to resolve segments that overlap the next one.
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum Overlaps {
+    /// End each segment when the next one starts
+    Clamp,
+    /// Combine overlapping segments into one
+    Merge,
+}
+
+/// Parses a signed duration such as `+1500ms`, `-2s` or `250ms` into milliseconds.
+pub fn parse_offset(s: &str) -> Result<i64, String> {
+    let (sign, rest) = match s.trim().strip_prefix('-') {
+        Some(rest) => (-1, rest),
+        None => (1, s.trim().trim_start_matches('+')),
+    };
+    let digits = rest
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(rest.len());
+    let (number, unit) = rest.split_at(digits);
+    let Ok(number) = number.parse::<i64>() else {
+        return Err(format!(
+            "expected a duration like +1500ms or -2s, found {s:?}"
+        ));
+    };
+    let scale = match unit {
+        "ms" => 1,
+        "s" => 1000,
+        _ => return Err("invalid duration unit; expected 's' or 'ms'".to_string()),
+    };
+    Ok(sign * number * scale)
+}
+
+/// Parses a clock value such as `00:05:00` or `12:30.5` into milliseconds.
+pub fn parse_time(s: &str) -> Result<u32, String> {
+    parse_clock_value(s).ok_or_else(|| format!("expected a time like 00:05:00, found {s:?}"))
+}
+
+/// Parses a positive scale factor such as `1.042709`.
+pub fn parse_factor(s: &str) -> Result<f64, String> {
+    match s.trim().parse::<f64>() {
+        Ok(factor) if factor.is_finite() && factor > 0.0 => Ok(factor),
+        _ => Err(format!("expected a positive factor like 1.04, found {s:?}")),
+    }
+}
+
+/// Multiplies `ms` by `factor`, rounding to the nearest millisecond.
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+pub fn scale(ms: u32, factor: f64) -> u32 {
+    (f64::from(ms) * factor)
+        .round()
+        .clamp(0.0, f64::from(u32::MAX)) as u32
+}
+
+/// Moves `ms` by `offset` milliseconds, stopping at zero.
+pub fn shift(ms: u32, offset: i64) -> u32 {
+    u32::try_from((i64::from(ms) + offset).max(0)).unwrap_or(u32::MAX)
+}
+
+/// A linear correction for a transcript that drifts against its media, worked out from two
+/// moments whose times in the transcript and in the media are both known.
+#[derive(Debug, Clone, Copy)]
+pub struct SyncPoints {
+    factor: f64,
+    offset: f64,
+}
+
+impl SyncPoints {
+    /// Parses `t1=t1',t2=t2'`, where each `t` is a clock value such as `1:02.5` as found in the
+    /// transcript, and each `t'` is where that moment really is.
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let usage = || format!("expected two anchors like 1:00=1:02,50:00=50:30, found {s:?}");
+        let [from1, middle, to2] = s.split('=').collect::<Vec<_>>()[..] else {
+            return Err(usage());
+        };
+        // Commas may also be decimal separators, so split where both sides are clock values
+        let (to1, from2) = middle
+            .match_indices(',')
+            .map(|(i, _)| (&middle[..i], &middle[i + 1..]))
+            .find(|(a, b)| parse_clock_value(a).is_some() && parse_clock_value(b).is_some())
+            .ok_or_else(usage)?;
+
+        let [from1, to1, from2, to2] = [from1, to1, from2, to2].map(parse_clock_value);
+        let (Some(from1), Some(to1), Some(from2), Some(to2)) = (from1, to1, from2, to2) else {
+            return Err(usage());
+        };
+        if from1 == from2 {
+            return Err("sync points must be at different times".to_string());
+        }
+
+        let [from1, to1, from2, to2] = [from1, to1, from2, to2].map(f64::from);
+        let factor = (to2 - to1) / (from2 - from1);
+        if factor <= 0.0 {
+            return Err(
+                "sync points must be in the same order in the transcript and the media".to_string(),
+            );
+        }
+        Ok(Self {
+            factor,
+            offset: to1 - factor * from1,
+        })
+    }
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn apply(self, ms: u32) -> u32 {
+        (f64::from(ms) * self.factor + self.offset)
+            .round()
+            .clamp(0.0, f64::from(u32::MAX)) as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_factor, parse_offset, parse_time, scale, shift, SyncPoints};
+
+    #[test]
+    fn parses_signed_offsets() {
+        assert_eq!(parse_offset("+1500ms"), Ok(1500));
+        assert_eq!(parse_offset("-2s"), Ok(-2000));
+        assert_eq!(parse_offset(" 250ms "), Ok(250));
+    }
+
+    #[test]
+    fn explains_malformed_offsets() {
+        let unit = Err("invalid duration unit; expected 's' or 'ms'".to_string());
+        assert_eq!(parse_offset("5"), unit);
+        assert_eq!(parse_offset("5m"), unit);
+        assert_eq!(parse_offset("1.5s"), unit);
+        for s in ["", "ms", "--2s", "+-2s"] {
+            assert_eq!(
+                parse_offset(s),
+                Err(format!(
+                    "expected a duration like +1500ms or -2s, found {s:?}"
+                ))
+            );
+        }
+    }
+
+    #[test]
+    fn shifting_stops_at_zero() {
+        assert_eq!(shift(1000, 500), 1500);
+        assert_eq!(shift(1000, -1500), 0);
+        assert_eq!(shift(u32::MAX, 1), u32::MAX);
+    }
+
+    #[test]
+    fn parses_positive_factors() {
+        assert_eq!(parse_factor(" 1.042709"), Ok(1.042_709));
+        for s in ["0", "-1", "inf", "NaN", "fast", ""] {
+            assert_eq!(
+                parse_factor(s),
+                Err(format!("expected a positive factor like 1.04, found {s:?}"))
+            );
+        }
+    }
+
+    #[test]
+    fn scaling_rounds_and_saturates() {
+        assert_eq!(scale(1000, 1.0015), 1002);
+        assert_eq!(scale(1000, 0.5), 500);
+        assert_eq!(scale(u32::MAX, 2.0), u32::MAX);
+    }
+
+    #[test]
+    fn sync_points_map_both_anchors() {
+        let sync = SyncPoints::parse("1:00=1:02,50:00=50:30").unwrap();
+        assert_eq!(sync.apply(60_000), 62_000);
+        assert_eq!(sync.apply(3_000_000), 3_030_000);
+        assert_eq!(sync.apply(0), 1_429);
+    }
+
+    #[test]
+    fn sync_points_accept_decimal_commas() {
+        let sync = SyncPoints::parse("0:01,5=0:02,0:11,5=0:12").unwrap();
+        assert_eq!(sync.apply(1_500), 2_000);
+        assert_eq!(sync.apply(6_500), 7_000);
+    }
+
+    #[test]
+    fn explains_malformed_sync_points() {
+        let err = |s| SyncPoints::parse(s).unwrap_err();
+        for s in [
+            "",
+            "1:00=1:02",
+            "1:00=1:02,",
+            "1:00=1:02;50:00=50:30",
+            "soon=1:02,50:00=50:30",
+        ] {
+            assert_eq!(
+                err(s),
+                format!("expected two anchors like 1:00=1:02,50:00=50:30, found {s:?}")
+            );
+        }
+        assert_eq!(
+            err("1:00=1:02,1:00=1:05"),
+            "sync points must be at different times"
+        );
+        assert_eq!(
+            err("1:00=50:30,50:00=1:02"),
+            "sync points must be in the same order in the transcript and the media"
+        );
+    }
+
+    #[test]
+    fn parses_range_times() {
+        assert_eq!(parse_time("00:05:00"), Ok(300_000));
+        assert_eq!(parse_time("12:30.5"), Ok(750_500));
+        assert_eq!(
+            parse_time("5 minutes"),
+            Err(r#"expected a time like 00:05:00, found "5 minutes""#.to_string())
+        );
+    }
+}
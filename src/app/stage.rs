@@ -0,0 +1,175 @@
+//! Stages that merge or split segments, given in order with `--pipeline`.
+use std::{fmt, time::Duration};
+
+use super::{expr::SplitWhen, input::ParseDuration};
+
+/// A stage that merges or splits segments, named like the option that otherwise enables it,
+/// e.g. `by-gap=2s` or `sentences`. The same stage may run more than once, e.g.
+/// `by-gap=500ms,lasting=10s,by-gap=5s`.
+#[derive(Debug, Clone)]
+pub enum Stage {
+    MinDuration(Duration),
+    /// Restores punctuation with --punctuate-cmd. This isn't named in --pipeline, where it
+    /// runs before the stages given.
+    Punctuate,
+    MaxSilence(Duration),
+    ByGap(Duration),
+    GapPercentile(f64),
+    SplitWhen(SplitWhen),
+    Sentences,
+    Clauses(usize),
+    MinWordCount(usize),
+    Lasting(Duration),
+    ChunkSize(usize),
+    MaxTokens(usize),
+    SplitSentences,
+    MaxWords(usize),
+    MaxChars(usize),
+    SplitLasting(Duration),
+    Words,
+}
+
+impl Stage {
+    /// Whether the stage reads all of its input before producing anything.
+    pub fn reads_all(&self) -> bool {
+        matches!(self, Self::GapPercentile(_))
+    }
+
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let (name, value) = match s.split_once('=') {
+            Some((name, value)) => (name.trim(), Some(value.trim())),
+            None => (s.trim(), None),
+        };
+        let flag = |stage| match value {
+            None => Ok(stage),
+            Some(_) => Err(format!("{name} doesn't take a value")),
+        };
+        let value = || value.ok_or_else(|| format!("{name} needs a value, e.g. {name}=N"));
+        let duration = || ParseDuration::parse(value()?).map_err(|e| format!("{name}: {e}"));
+        let number = || {
+            value()?
+                .parse::<usize>()
+                .map_err(|e| format!("{name}: {e}"))
+        };
+
+        let stage = match name {
+            "min-duration" => Self::MinDuration(duration()?),
+            "max-silence" => Self::MaxSilence(duration()?),
+            "by-gap" => Self::ByGap(duration()?),
+            "gap-percentile" => {
+                Self::GapPercentile(value()?.parse().map_err(|e| format!("{name}: {e}"))?)
+            }
+            "split-when" => Self::SplitWhen(SplitWhen::parse(value()?)?),
+            "sentences" => flag(Self::Sentences)?,
+            "clauses" => Self::Clauses(number()?),
+            "min-word-count" => Self::MinWordCount(number()?),
+            "lasting" => Self::Lasting(duration()?),
+            "chunk-size" => Self::ChunkSize(number()?),
+            "max-tokens" => Self::MaxTokens(number()?),
+            "split-sentences" => flag(Self::SplitSentences)?,
+            "max-words" => Self::MaxWords(number()?),
+            "max-chars" => Self::MaxChars(number()?),
+            "split-lasting" => Self::SplitLasting(duration()?),
+            "words" => flag(Self::Words)?,
+            _ => return Err(format!("unknown pipeline stage {name:?}")),
+        };
+        Ok(stage)
+    }
+}
+
+impl fmt::Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let duration = |d: &Duration| match d.as_millis() {
+            ms if ms % 1000 == 0 => format!("{}s", ms / 1000),
+            ms => format!("{ms}ms"),
+        };
+        match self {
+            Self::MinDuration(d) => write!(f, "min-duration={}", duration(d)),
+            Self::Punctuate => write!(f, "punctuate"),
+            Self::MaxSilence(d) => write!(f, "max-silence={}", duration(d)),
+            Self::ByGap(d) => write!(f, "by-gap={}", duration(d)),
+            Self::GapPercentile(p) => write!(f, "gap-percentile={p}"),
+            Self::SplitWhen(condition) => write!(f, "split-when={condition}"),
+            Self::Sentences => write!(f, "sentences"),
+            Self::Clauses(n) => write!(f, "clauses={n}"),
+            Self::MinWordCount(n) => write!(f, "min-word-count={n}"),
+            Self::Lasting(d) => write!(f, "lasting={}", duration(d)),
+            Self::ChunkSize(n) => write!(f, "chunk-size={n}"),
+            Self::MaxTokens(n) => write!(f, "max-tokens={n}"),
+            Self::SplitSentences => write!(f, "split-sentences"),
+            Self::MaxWords(n) => write!(f, "max-words={n}"),
+            Self::MaxChars(n) => write!(f, "max-chars={n}"),
+            Self::SplitLasting(d) => write!(f, "split-lasting={}", duration(d)),
+            Self::Words => write!(f, "words"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::Stage;
+
+    #[test]
+    fn parses_stages_with_and_without_values() {
+        let parse = |s| Stage::parse(s).unwrap();
+        assert!(matches!(parse("by-gap=2s"), Stage::ByGap(d) if d == Duration::from_secs(2)));
+        assert!(
+            matches!(parse(" lasting = 1500ms "), Stage::Lasting(d) if d == Duration::from_millis(1500))
+        );
+        assert!(
+            matches!(parse("gap-percentile=90"), Stage::GapPercentile(p) if (p - 90.0).abs() < f64::EPSILON)
+        );
+        assert!(matches!(parse("max-chars=42"), Stage::MaxChars(42)));
+        assert!(matches!(
+            parse("split-when=gap > 2s || words > 40"),
+            Stage::SplitWhen(_)
+        ));
+        assert!(matches!(parse("sentences"), Stage::Sentences));
+        assert!(matches!(parse("words"), Stage::Words));
+    }
+
+    #[test]
+    fn displays_stages_as_written() {
+        for s in [
+            "by-gap=2s",
+            "max-silence=500ms",
+            "gap-percentile=90",
+            "split-when=gap > 2s || words > 40",
+            "sentences",
+            "max-chars=42",
+            "words",
+        ] {
+            assert_eq!(Stage::parse(s).unwrap().to_string(), s);
+        }
+        assert_eq!(
+            Stage::parse(" lasting = 1500ms ").unwrap().to_string(),
+            "lasting=1500ms"
+        );
+    }
+
+    #[test]
+    fn explains_malformed_stages() {
+        let err = |s| Stage::parse(s).unwrap_err();
+        assert_eq!(err(""), r#"unknown pipeline stage """#);
+        assert_eq!(err("merge=2s"), r#"unknown pipeline stage "merge""#);
+        assert_eq!(err("by-gap"), "by-gap needs a value, e.g. by-gap=N");
+        assert_eq!(err("by-gap=2"), "by-gap: no unit found in value");
+        assert_eq!(
+            err("by-gap=2m"),
+            "by-gap: invalid duration unit; expected 's' or 'ms'"
+        );
+        assert_eq!(err("by-gap=-2s"), "by-gap: no digits found in value");
+        assert_eq!(err("by-gap=+2s"), "by-gap: no digits found in value");
+        assert_eq!(err("sentences=3"), "sentences doesn't take a value");
+        assert_eq!(
+            err("max-words=many"),
+            "max-words: invalid digit found in string"
+        );
+        assert_eq!(
+            err("split-when=gap >"),
+            r#"expected a number to compare "gap" with"#
+        );
+    }
+}
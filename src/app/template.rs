@@ -0,0 +1,229 @@
+//! User-defined line formats.
+use std::io;
+
+use super::timecode::FrameRate;
+use crate::transcribe::{format_clock_value, Timing};
+
+#[derive(Debug, Clone, Copy)]
+enum Time {
+    Start,
+    End,
+    Duration,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Unit {
+    /// The same clock value as pretty output, e.g. `1:02.50`.
+    Clock,
+    Milliseconds,
+    Seconds,
+    /// Always with hours and milliseconds, e.g. `00:01:02.500`.
+    Hms,
+    /// SMPTE timecode at the output frame rate, e.g. `00:01:02:12`.
+    Timecode,
+}
+
+#[derive(Debug, Clone)]
+enum Piece {
+    Literal(String),
+    Time(Time, Unit),
+    Index,
+    Text,
+    Words,
+    Speaker,
+}
+
+/// A line format such as `{start_ms}\t{end_hms}\t{text}`, written once per timing.
+///
+/// Times are available as `{start}`, `{end}` and `{duration}`, with the suffixes `_ms`, `_s`,
+/// `_hms` and `_tc` for milliseconds, seconds, `HH:MM:SS.mmm` and SMPTE timecode. `{index}` is
+/// the 1-based position, `{words}` the word count, and `{text}` and `{speaker}` are what they
+/// say. `{{` and `}}` are literal braces, and `\t`, `\n` and `\\` are escapes.
+#[derive(Debug, Clone)]
+pub struct Template {
+    pieces: Vec<Piece>,
+}
+
+impl Template {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let mut pieces = vec![];
+        let mut literal = String::new();
+        let mut chars = s.chars();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    Some('t') => literal.push('\t'),
+                    Some('n') => literal.push('\n'),
+                    Some('\\') | None => literal.push('\\'),
+                    Some(other) => {
+                        literal.push('\\');
+                        literal.push(other);
+                    }
+                },
+                '{' if chars.as_str().starts_with('{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.as_str().starts_with('}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let rest = chars.as_str();
+                    let Some(close) = rest.find('}') else {
+                        return Err("unclosed '{' in template".to_string());
+                    };
+                    if !literal.is_empty() {
+                        pieces.push(Piece::Literal(std::mem::take(&mut literal)));
+                    }
+                    pieces.push(Self::placeholder(&rest[..close])?);
+                    chars = rest[close + 1..].chars();
+                }
+                '}' => return Err("unmatched '}' in template; use '}}' for a brace".to_string()),
+                c => literal.push(c),
+            }
+        }
+
+        if !literal.is_empty() {
+            pieces.push(Piece::Literal(literal));
+        }
+        Ok(Self { pieces })
+    }
+
+    fn placeholder(name: &str) -> Result<Piece, String> {
+        let (time, unit) = name.split_once('_').unwrap_or((name, ""));
+        let time = match time {
+            "start" => Time::Start,
+            "end" => Time::End,
+            "duration" => Time::Duration,
+            _ => {
+                return match name {
+                    "index" => Ok(Piece::Index),
+                    "text" => Ok(Piece::Text),
+                    "words" => Ok(Piece::Words),
+                    "speaker" => Ok(Piece::Speaker),
+                    _ => Err(format!("unknown template placeholder {{{name}}}")),
+                }
+            }
+        };
+        let unit = match unit {
+            "" => Unit::Clock,
+            "ms" => Unit::Milliseconds,
+            "s" => Unit::Seconds,
+            "hms" => Unit::Hms,
+            "tc" => Unit::Timecode,
+            _ => {
+                return Err(format!(
+                    "unknown time unit in {{{name}}}; use _ms, _s, _hms or _tc"
+                ))
+            }
+        };
+        Ok(Piece::Time(time, unit))
+    }
+
+    fn render(&self, index: usize, t: &Timing, rate: FrameRate) -> String {
+        self.pieces
+            .iter()
+            .map(|piece| match piece {
+                Piece::Literal(s) => s.clone(),
+                Piece::Time(time, unit) => {
+                    let ms = match time {
+                        Time::Start => t.start(),
+                        Time::End => t.end(),
+                        Time::Duration => t.duration(),
+                    };
+                    match unit {
+                        Unit::Clock => format_clock_value(ms, None),
+                        Unit::Milliseconds => ms.to_string(),
+                        Unit::Seconds => format!("{:.3}", f64::from(ms) / 1000.0),
+                        Unit::Hms => {
+                            let s = ms / 1000;
+                            format!(
+                                "{:02}:{:02}:{:02}.{:03}",
+                                s / 3600,
+                                s / 60 % 60,
+                                s % 60,
+                                ms % 1000
+                            )
+                        }
+                        Unit::Timecode => rate.timecode(rate.frames(ms)).to_string(),
+                    }
+                }
+                Piece::Index => index.to_string(),
+                Piece::Text => t.content().to_string(),
+                Piece::Words => t.content().split_whitespace().count().to_string(),
+                Piece::Speaker => t.speaker().unwrap_or_default().to_string(),
+            })
+            .collect()
+    }
+
+    pub fn write<W: io::Write>(
+        &self,
+        timings: impl Iterator<Item = Timing>,
+        mut w: W,
+        rate: FrameRate,
+    ) -> io::Result<()> {
+        for (i, t) in (1..).zip(timings) {
+            writeln!(w, "{}", self.render(i, &t, rate))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FrameRate, Template};
+    use crate::transcribe::Timing;
+
+    fn render(template: &str, t: &Timing) -> String {
+        Template::parse(template)
+            .unwrap()
+            .render(3, t, FrameRate::PAL)
+    }
+
+    #[test]
+    fn renders_times_in_each_unit() {
+        let t = Timing::new(61_500, 3_723_250, "x".to_string());
+        assert_eq!(render("{start} {end}", &t), "1:01.50 62:03.25");
+        assert_eq!(render("{start_ms} {duration_ms}", &t), "61500 3661750");
+        assert_eq!(render("{start_s}", &t), "61.500");
+        assert_eq!(render("{end_hms}", &t), "01:02:03.250");
+    }
+
+    #[test]
+    fn renders_timecode_at_the_frame_rate() {
+        let t = Timing::new(1_000, 61_000, "x".to_string());
+        let template = Template::parse("{start_tc}-{end_tc}").unwrap();
+        assert_eq!(
+            template.render(1, &t, FrameRate::PAL),
+            "00:00:01:00-00:01:01:00"
+        );
+        let ntsc = FrameRate::parse("29.97").unwrap();
+        assert_eq!(template.render(1, &t, ntsc), "00:00:01;00-00:01:01;00");
+    }
+
+    #[test]
+    fn renders_text_and_escapes() {
+        let t =
+            Timing::new(0, 1000, " Hello there".to_string()).with_speaker(Some("Ann".to_string()));
+        assert_eq!(
+            render(r"{index}\t{speaker}: {text} ({words}) {{x}}\n", &t),
+            "3\tAnn: Hello there (2) {x}\n"
+        );
+        assert_eq!(render(r"a\b\\", &t), r"a\b\");
+    }
+
+    #[test]
+    fn explains_malformed_templates() {
+        let err = |s| Template::parse(s).unwrap_err();
+        assert_eq!(err("{start"), "unclosed '{' in template");
+        assert_eq!(err("}"), "unmatched '}' in template; use '}}' for a brace");
+        assert_eq!(err("{nope}"), "unknown template placeholder {nope}");
+        assert_eq!(err("{index_ms}"), "unknown template placeholder {index_ms}");
+        assert_eq!(
+            err("{start_min}"),
+            "unknown time unit in {start_min}; use _ms, _s, _hms or _tc"
+        );
+    }
+}
@@ -0,0 +1,89 @@
+const RLM: char = '\u{200F}';
+const RLE: char = '\u{202B}';
+const PDF: char = '\u{202C}';
+
+/// Directional formatting inserted into right-to-left subtitle lines.
+///
+/// Players that lay out every cue left-to-right will otherwise move leading and trailing
+/// punctuation of Arabic or Hebrew text to the wrong side of the line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum RtlMarks {
+    /// Surrounds each line with right-to-left marks
+    Rlm,
+    /// Wraps each line in a right-to-left embedding
+    Rle,
+}
+
+impl RtlMarks {
+    /// Marks every right-to-left line of `text`, or every line when `force` is set.
+    pub fn apply(self, text: &str, force: bool) -> String {
+        let body = text.trim_start();
+        let indent = &text[..text.len() - body.len()];
+
+        let lines = body
+            .split('\n')
+            .map(|line| {
+                if line.is_empty() || !(force || is_rtl(line)) {
+                    return line.to_string();
+                }
+                // Marks already in the line would nest inside the new ones
+                let line = line.replace(is_directional, "");
+                match self {
+                    Self::Rlm => format!("{RLM}{line}{RLM}"),
+                    Self::Rle => format!("{RLE}{line}{PDF}"),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        format!("{indent}{}", lines.join("\n"))
+    }
+}
+
+/// Whether `c` is an invisible character that sets the direction of the text around it.
+pub fn is_directional(c: char) -> bool {
+    matches!(c, '\u{200E}' | '\u{200F}' | '\u{202A}'..='\u{202E}')
+}
+
+/// Whether the first strongly-directional character of `line` is right-to-left.
+pub fn is_rtl(line: &str) -> bool {
+    line.chars()
+        .find(|c| c.is_alphabetic())
+        .is_some_and(|c| {
+            matches!(c, '\u{0590}'..='\u{08FF}' | '\u{FB1D}'..='\u{FDFF}' | '\u{FE70}'..='\u{FEFF}')
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_rtl, RtlMarks};
+
+    #[test]
+    fn marks_only_right_to_left_lines() {
+        assert_eq!(
+            RtlMarks::Rlm.apply(" שלום!\nhello", false),
+            " \u{200F}שלום!\u{200F}\nhello"
+        );
+        assert_eq!(RtlMarks::Rle.apply("مرحبا", false), "\u{202B}مرحبا\u{202C}");
+        assert_eq!(RtlMarks::Rlm.apply("hi", true), "\u{200F}hi\u{200F}");
+    }
+
+    #[test]
+    fn replaces_existing_marks() {
+        assert_eq!(
+            RtlMarks::Rlm.apply("\u{200F}שלום\u{200F}", false),
+            "\u{200F}שלום\u{200F}"
+        );
+        assert_eq!(
+            RtlMarks::Rle.apply("\u{202B}שלום\u{202C}", false),
+            "\u{202B}שלום\u{202C}"
+        );
+    }
+
+    #[test]
+    fn direction_follows_the_first_letter() {
+        assert!(is_rtl("42. שלום hello"));
+        assert!(!is_rtl("hello שלום"));
+        assert!(!is_rtl("123 ..."));
+        assert!(!is_rtl(""));
+    }
+}
@@ -0,0 +1,104 @@
+use super::{map_words, sentence_breaks};
+use crate::transcribe::SentenceEnds;
+
+/// A capitalization style to rewrite text in.
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum Case {
+    Lower,
+    Upper,
+    /// Lowercase, except for the first letter of each sentence and the pronoun "I"
+    Sentence,
+}
+
+impl Case {
+    pub fn apply(self, text: &str, ends: &SentenceEnds) -> String {
+        match self {
+            Self::Lower => text.to_lowercase(),
+            Self::Upper => text.to_uppercase(),
+            Self::Sentence => {
+                let text = capitalize_sentences(&text.to_lowercase(), ends);
+                map_words(&text, |word| {
+                    (word == "i" || word.starts_with("i'")).then(|| format!("I{}", &word[1..]))
+                })
+            }
+        }
+    }
+}
+
+/// Uppercases the first letter of `text` and the first letter following each sentence ending.
+pub fn capitalize_sentences(text: &str, ends: &SentenceEnds) -> String {
+    let mut breaks = sentence_breaks(text, ends).peekable();
+    let mut capitalize = true;
+    let mut out = String::with_capacity(text.len());
+
+    for (i, c) in text.char_indices() {
+        if breaks.next_if_eq(&i).is_some() {
+            capitalize = true;
+        }
+        if capitalize && c.is_alphabetic() {
+            out.extend(c.to_uppercase());
+            capitalize = false;
+            continue;
+        }
+
+        if c.is_alphanumeric() {
+            capitalize = false;
+        }
+        out.push(c);
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{capitalize_sentences, Case};
+    use crate::transcribe::SentenceEnds;
+
+    fn capitalize(text: &str) -> String {
+        capitalize_sentences(text, &SentenceEnds::default())
+    }
+
+    #[test]
+    fn capitalizes_sentence_starts() {
+        assert_eq!(
+            capitalize(" well. okay! 3 apples? yes"),
+            " Well. Okay! 3 apples? Yes"
+        );
+        assert_eq!(capitalize("... ?!"), "... ?!");
+        assert_eq!(capitalize(" it costs 3.5 euros"), " It costs 3.5 euros");
+        assert_eq!(capitalize(" wait... then"), " Wait... then");
+    }
+
+    #[test]
+    fn capitalizes_after_the_configured_endings() {
+        assert_eq!(capitalize(" 好。ok！sure"), " 好。Ok！Sure");
+        let ends = SentenceEnds::new(";…".chars());
+        assert_eq!(
+            capitalize_sentences(" one; two. three… four", &ends),
+            " One; Two. three… Four"
+        );
+    }
+
+    #[test]
+    fn rewrites_in_each_case() {
+        let ends = SentenceEnds::default();
+        let text = " HELLO there. i think i'm FINE";
+        assert_eq!(
+            Case::Lower.apply(text, &ends),
+            " hello there. i think i'm fine"
+        );
+        assert_eq!(
+            Case::Upper.apply(text, &ends),
+            " HELLO THERE. I THINK I'M FINE"
+        );
+        assert_eq!(
+            Case::Sentence.apply(text, &ends),
+            " Hello there. I think I'm fine"
+        );
+        assert_eq!(
+            Case::Sentence.apply(" iPhone, ibis", &ends),
+            " Iphone, ibis"
+        );
+    }
+}
@@ -0,0 +1,112 @@
+use std::{
+    collections::HashSet,
+    io::{self, BufRead},
+};
+
+use super::{edit_distance, map_words, match_case};
+
+/// A user-supplied vocabulary used to correct misheard words.
+///
+/// Words found in the dictionary (ignoring case) are left alone. Any other word is replaced by
+/// its nearest dictionary entry, provided that entry is close enough by edit distance.
+#[derive(Debug, Clone)]
+pub struct Dictionary {
+    words: Vec<String>,
+    known: HashSet<String>,
+}
+
+impl Dictionary {
+    pub fn from_reader<R: io::Read>(reader: R) -> io::Result<Self> {
+        let mut words = vec![];
+        for line in io::BufReader::new(reader).lines() {
+            let line = line?;
+            let word = line.trim();
+            if word.is_empty() || word.starts_with('#') {
+                continue;
+            }
+            words.push(word.to_string());
+        }
+
+        let known = words.iter().map(|w| w.to_lowercase()).collect();
+        Ok(Self { words, known })
+    }
+
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let file = std::fs::File::open(s).map_err(|e| format!("couldn't open {s}: {e}"))?;
+        Self::from_reader(file).map_err(|e| format!("couldn't read {s}: {e}"))
+    }
+
+    /// Corrects every out-of-vocabulary word in `text`.
+    ///
+    /// A word is only replaced when its nearest entry is at most `max_distance` edits away and
+    /// no more than one edit per four characters, so short common words are never rewritten.
+    pub fn correct(&self, text: &str, max_distance: usize) -> String {
+        map_words(text, |word| {
+            if word.chars().any(|c| c.is_ascii_digit()) || self.known.contains(&word.to_lowercase())
+            {
+                return None;
+            }
+
+            let lower = word.to_lowercase();
+            let allowed = max_distance.min(lower.chars().count() / 4);
+            if allowed == 0 {
+                return None;
+            }
+
+            let mut best: Option<(usize, &str)> = None;
+            let mut tied = false;
+            for candidate in &self.words {
+                let distance = edit_distance(&lower, &candidate.to_lowercase());
+                match best {
+                    _ if distance > allowed => {}
+                    Some((d, _)) if distance > d => {}
+                    Some((d, _)) if distance == d => tied = true,
+                    _ => {
+                        best = Some((distance, candidate));
+                        tied = false;
+                    }
+                }
+            }
+
+            // An ambiguous correction is worse than none at all
+            best.filter(|_| !tied)
+                .map(|(_, candidate)| match_case(word, candidate))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Dictionary;
+
+    fn dictionary(words: &str) -> Dictionary {
+        Dictionary::from_reader(words.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn corrects_words_close_to_an_entry() {
+        let dictionary = dictionary("# terms\nKubernetes\nPostgres\n\n");
+        assert_eq!(
+            dictionary.correct(" Kubernetis and postgress run", 2),
+            " Kubernetes and Postgres run"
+        );
+        assert_eq!(dictionary.correct(" Kubernetis", 0), " Kubernetis");
+    }
+
+    #[test]
+    fn leaves_ambiguous_and_short_words_alone() {
+        let dictionary = dictionary("bark\npark\nmain");
+        assert_eq!(dictionary.correct(" barks", 2), " bark");
+        assert_eq!(dictionary.correct(" dark", 2), " dark");
+        assert_eq!(dictionary.correct(" man v2", 2), " man v2");
+    }
+
+    #[test]
+    fn reports_a_missing_file() {
+        let err = Dictionary::parse("/nonexistent/dictionary.txt").unwrap_err();
+        assert!(
+            err.starts_with("couldn't open /nonexistent/dictionary.txt: "),
+            "{err}"
+        );
+    }
+}
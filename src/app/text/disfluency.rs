@@ -0,0 +1,115 @@
+//! Cleaning up the hesitations of spontaneous speech, for reading copy.
+use std::borrow::Cow;
+
+/// Collapses words and phrases of up to `max_len` words that are said again straight away, as
+/// in "I I I think" or "you know you know", keeping the last time each is said in the
+/// capitalization of the first.
+///
+/// Stutter fragments are dropped too, whether written as a word of their own ("th- the") or
+/// as single letters joined to the word ("b-b-but").
+pub fn collapse_repeats(text: &str, max_len: usize) -> String {
+    let words = text
+        .split_whitespace()
+        .map(strip_stutter)
+        .collect::<Vec<_>>();
+
+    let mut kept: Vec<Cow<str>> = vec![];
+    for (i, &word) in words.iter().enumerate() {
+        if words
+            .get(i + 1)
+            .is_some_and(|&next| is_fragment_of(word, next))
+        {
+            continue;
+        }
+        kept.push(Cow::Borrowed(word));
+
+        // Each word may complete a repeat of what came just before it
+        while let Some(len) = (1..=max_len.min(kept.len() / 2)).find(|&len| {
+            let n = kept.len();
+            (0..len).all(|k| same_word(&kept[n - 2 * len + k], &kept[n - len + k]))
+        }) {
+            let n = kept.len();
+            let original = kept.drain(n - 2 * len..n - len).next();
+            if let Some(original) = original {
+                let first = &mut kept[n - 2 * len];
+                *first = Cow::Owned(super::match_case(&original, first));
+            }
+        }
+    }
+
+    let indent = &text[..text.len() - text.trim_start().len()];
+    format!("{indent}{}", kept.join(" "))
+}
+
+/// The word a stutter such as "b-b-but" leads up to, or `word` itself.
+fn strip_stutter(word: &str) -> &str {
+    let Some((fragments, rest)) = word.rsplit_once('-') else {
+        return word;
+    };
+    let Some(first) = rest.chars().next() else {
+        return word;
+    };
+    let stutters = fragments.split('-').all(|fragment| {
+        let mut chars = fragment.chars();
+        matches!((chars.next(), chars.next()), (Some(c), None) if c.to_lowercase().eq(first.to_lowercase()))
+    });
+    if stutters {
+        rest
+    } else {
+        word
+    }
+}
+
+/// Whether `word` is a false start such as "th-" of the word `next`.
+fn is_fragment_of(word: &str, next: &str) -> bool {
+    let Some(stem) = word
+        .strip_suffix('-')
+        .map(bare)
+        .filter(|stem| !stem.is_empty())
+    else {
+        return false;
+    };
+    bare(next).starts_with(&stem)
+}
+
+fn same_word(a: &str, b: &str) -> bool {
+    let a = bare(a);
+    !a.is_empty() && a == bare(b)
+}
+
+/// `word` in lowercase, without surrounding punctuation.
+fn bare(word: &str) -> String {
+    word.trim_matches(|c: char| !c.is_alphanumeric())
+        .to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::collapse_repeats;
+
+    #[test]
+    fn collapses_repeated_words_and_phrases() {
+        assert_eq!(collapse_repeats(" I I I think so", 3), " I think so");
+        assert_eq!(
+            collapse_repeats(" you know, you know it works", 3),
+            " you know it works"
+        );
+        assert_eq!(collapse_repeats(" The the end", 3), " The end");
+        assert_eq!(collapse_repeats(" very very good", 0), " very very good");
+    }
+
+    #[test]
+    fn drops_stutters() {
+        assert_eq!(collapse_repeats(" b-b-but th- then", 3), " but then");
+        assert_eq!(
+            collapse_repeats(" well-known x-ray", 3),
+            " well-known x-ray"
+        );
+    }
+
+    #[test]
+    fn leaves_text_without_words_alone() {
+        assert_eq!(collapse_repeats("", 3), "");
+        assert_eq!(collapse_repeats(" - -- ...", 3), " - -- ...");
+    }
+}
@@ -0,0 +1,159 @@
+use std::io;
+
+use regex::{Captures, NoExpand, Regex, RegexBuilder};
+
+use super::match_case;
+
+/// A shared list of substitutions, loaded from a headerless CSV file.
+///
+/// Each record is `wrong,right[,flags]`, where flags may contain `c` to match case-sensitively
+/// and `w` to only match whole words. Lines starting with `#` are ignored.
+///
+/// ```csv
+/// # wrong,right,flags
+/// kuber netties,Kubernetes
+/// sequel,SQL,cw
+/// ```
+///
+/// A corrections file is instead a headerless TSV of `wrong\tright` pairs, which always match
+/// whole words regardless of case, and take on the capitalization of the words they replace.
+#[derive(Debug, Clone)]
+pub struct Glossary {
+    entries: Vec<Entry>,
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    re: Regex,
+    right: String,
+    /// Whether `right` takes on the capitalization of the text it replaces.
+    keep_case: bool,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct Record {
+    wrong: String,
+    right: String,
+    #[serde(default)]
+    flags: String,
+}
+
+impl Glossary {
+    pub fn from_reader<R: io::Read>(reader: R) -> Result<Self, String> {
+        Self::read(reader, b',', false)
+    }
+
+    /// Reads a corrections file of `wrong\tright` pairs.
+    pub fn corrections_from_reader<R: io::Read>(reader: R) -> Result<Self, String> {
+        Self::read(reader, b'\t', true)
+    }
+
+    fn read<R: io::Read>(reader: R, delimiter: u8, corrections: bool) -> Result<Self, String> {
+        let mut rdr = csv::ReaderBuilder::new()
+            .delimiter(delimiter)
+            .has_headers(false)
+            .flexible(true)
+            .comment(Some(b'#'))
+            .trim(csv::Trim::Fields)
+            .from_reader(reader);
+
+        let mut entries = vec![];
+        for record in rdr.deserialize() {
+            let Record {
+                wrong,
+                right,
+                flags,
+            } = record.map_err(|e| e.to_string())?;
+            let flags = if corrections { "w".to_string() } else { flags };
+
+            if let Some(flag) = flags.chars().find(|c| !matches!(c, 'c' | 'w')) {
+                return Err(format!("unknown glossary flag '{flag}' for '{wrong}'"));
+            }
+
+            let pattern = if flags.contains('w') {
+                format!(r"\b{}\b", regex::escape(&wrong))
+            } else {
+                regex::escape(&wrong)
+            };
+
+            let re = RegexBuilder::new(&pattern)
+                .case_insensitive(!flags.contains('c'))
+                .build()
+                .map_err(|e| e.to_string())?;
+
+            entries.push(Entry {
+                re,
+                right,
+                keep_case: corrections,
+            });
+        }
+
+        Ok(Self { entries })
+    }
+
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let file = std::fs::File::open(s).map_err(|e| format!("couldn't open {s}: {e}"))?;
+        Self::from_reader(file).map_err(|e| format!("couldn't read {s}: {e}"))
+    }
+
+    pub fn parse_corrections(s: &str) -> Result<Self, String> {
+        let file = std::fs::File::open(s).map_err(|e| format!("couldn't open {s}: {e}"))?;
+        Self::corrections_from_reader(file).map_err(|e| format!("couldn't read {s}: {e}"))
+    }
+
+    /// Applies every substitution, in file order, to `text`.
+    pub fn apply(&self, text: &str) -> String {
+        self.entries.iter().fold(text.to_string(), |acc, entry| {
+            if entry.keep_case {
+                entry
+                    .re
+                    .replace_all(&acc, |caps: &Captures| match_case(&caps[0], &entry.right))
+                    .into_owned()
+            } else {
+                entry
+                    .re
+                    .replace_all(&acc, NoExpand(&entry.right))
+                    .into_owned()
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Glossary;
+
+    #[test]
+    fn applies_substitutions_with_flags() {
+        let glossary = Glossary::from_reader(
+            "# wrong,right,flags\nkuber netties,Kubernetes\nsequel,SQL,cw\n".as_bytes(),
+        )
+        .unwrap();
+        assert_eq!(
+            glossary.apply(" Kuber Netties and sequel, not Sequel or sequels"),
+            " Kubernetes and SQL, not Sequel or sequels"
+        );
+    }
+
+    #[test]
+    fn keeps_the_case_of_corrected_words() {
+        let corrections = Glossary::corrections_from_reader("teh\tthe\n".as_bytes()).unwrap();
+        assert_eq!(
+            corrections.apply(" Teh TEH teh tehran"),
+            " The THE the tehran"
+        );
+        let err = Glossary::corrections_from_reader("teh\n".as_bytes()).unwrap_err();
+        assert!(err.contains("record 0 (line: 1, byte: 0)"), "{err}");
+    }
+
+    #[test]
+    fn reports_unknown_flags_and_missing_files() {
+        let err = Glossary::from_reader("a,b,x\n".as_bytes()).unwrap_err();
+        assert_eq!(err, "unknown glossary flag 'x' for 'a'");
+        let err = Glossary::parse("/nonexistent/glossary.csv").unwrap_err();
+        assert!(
+            err.starts_with("couldn't open /nonexistent/glossary.csv: "),
+            "{err}"
+        );
+    }
+}
@@ -0,0 +1,193 @@
+//! Text-level corrections applied to the content of each segment.
+pub mod bidi;
+pub mod case;
+pub mod dictionary;
+pub mod disfluency;
+pub mod glossary;
+pub mod numbers;
+pub mod punctuate;
+pub mod replace;
+pub mod sanitize;
+pub mod unicode;
+pub mod wrap;
+
+use crate::transcribe::SentenceEnds;
+
+/// Rewrites each word of `text` using `f`, leaving whitespace and punctuation untouched.
+///
+/// A word is a run of alphanumeric characters, optionally joined by apostrophes. When `f`
+/// returns `None` the word is kept as-is.
+pub fn map_words<F>(text: &str, mut f: F) -> String
+where
+    F: FnMut(&str) -> Option<String>,
+{
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find(char::is_alphanumeric) {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let len = word_len(rest);
+        let word = &rest[..len];
+        match f(word) {
+            Some(replacement) => out.push_str(&replacement),
+            None => out.push_str(word),
+        }
+        rest = &rest[len..];
+    }
+
+    out.push_str(rest);
+    out
+}
+
+/// The byte offsets just past each sentence ending in `text`, judged a whitespace-separated
+/// word at a time as `--sentences` does. An ASCII terminator only counts when whitespace or the
+/// end of the text follows it, so that `3.5` stays whole, while `。` and the like needn't be
+/// followed by a space.
+pub fn sentence_breaks<'a>(
+    text: &'a str,
+    ends: &'a SentenceEnds,
+) -> impl Iterator<Item = usize> + 'a {
+    let mut word_start = 0;
+    text.char_indices().filter_map(move |(i, c)| {
+        let end = i + c.len_utf8();
+        if c.is_whitespace() {
+            word_start = end;
+            return None;
+        }
+        let boundary = !c.is_ascii() || text[end..].chars().next().is_none_or(char::is_whitespace);
+        (boundary && ends.is_end(&text[word_start..end])).then_some(end)
+    })
+}
+
+fn word_len(s: &str) -> usize {
+    let mut len = 0;
+    let mut chars = s.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        let joined = c == '\'' && chars.peek().is_some_and(|(_, n)| n.is_alphanumeric());
+        if !(c.is_alphanumeric() || joined && i > 0) {
+            break;
+        }
+        len = i + c.len_utf8();
+    }
+    len
+}
+
+/// Applies the capitalization of `original` to `replacement`.
+///
+/// All-caps words stay all-caps and capitalized words stay capitalized; otherwise the
+/// replacement is used verbatim.
+pub fn match_case(original: &str, replacement: &str) -> String {
+    let mut letters = original.chars().filter(|c| c.is_alphabetic());
+    let Some(first) = letters.next() else {
+        return replacement.to_string();
+    };
+
+    if first.is_uppercase() && original.chars().count() > 1 && letters.all(char::is_uppercase) {
+        replacement.to_uppercase()
+    } else if first.is_uppercase() {
+        let mut chars = replacement.chars();
+        chars
+            .next()
+            .map(|c| c.to_uppercase().chain(chars).collect())
+            .unwrap_or_default()
+    } else {
+        replacement.to_string()
+    }
+}
+
+/// Collapses each run of spaces and tabs into a single space and trims every line, dropping
+/// empty ones.
+///
+/// The result starts with a single space, as segment text does to mark a word boundary,
+/// unless nothing is left of it.
+pub fn normalize_space(text: &str) -> String {
+    let lines = text
+        .lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>();
+    if lines.is_empty() {
+        return String::new();
+    }
+    format!(" {}", lines.join("\n"))
+}
+
+/// Levenshtein distance between two strings, counted in characters.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut curr = vec![0; b.len() + 1];
+
+    for (i, ca) in a.chars().enumerate() {
+        curr[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = prev[j] + usize::from(ca != *cb);
+            curr[j + 1] = substitution.min(prev[j + 1] + 1).min(curr[j] + 1);
+        }
+        std::mem::swap(&mut prev, &mut curr);
+    }
+
+    prev[b.len()]
+}
+
+/// How alike two segment texts are, from 0 (nothing in common) to 1 (the same words), ignoring
+/// case, punctuation and spacing.
+pub fn similarity(a: &str, b: &str) -> f64 {
+    let normalize = |s: &str| {
+        s.split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .map(str::to_lowercase)
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    let (a, b) = (normalize(a), normalize(b));
+    let len = a.chars().count().max(b.chars().count());
+    if len == 0 {
+        return 1.0;
+    }
+    #[allow(clippy::cast_precision_loss)]
+    let distance = edit_distance(&a, &b) as f64 / len as f64;
+    1.0 - distance
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{edit_distance, map_words, match_case, normalize_space, similarity};
+
+    #[test]
+    fn maps_whole_words() {
+        let upper = |w: &str| (w != "it's").then(|| w.to_uppercase());
+        assert_eq!(
+            map_words(" it's a 'test', ok?", upper),
+            " it's A 'TEST', OK?"
+        );
+        assert_eq!(map_words("", upper), "");
+        assert_eq!(map_words("... !", upper), "... !");
+    }
+
+    #[test]
+    fn matches_case() {
+        assert_eq!(match_case("NASA", "nasa"), "NASA");
+        assert_eq!(match_case("Kubernetes", "kubernetes"), "Kubernetes");
+        assert_eq!(match_case("iphone", "iPhone"), "iPhone");
+        assert_eq!(match_case("A", "an"), "An");
+        assert_eq!(match_case("42", "forty-two"), "forty-two");
+    }
+
+    #[test]
+    fn normalizes_space() {
+        assert_eq!(normalize_space("  a \t b \n\n  c  "), " a b\nc");
+        assert_eq!(normalize_space(" \t\n "), "");
+    }
+
+    #[test]
+    fn measures_distance_and_similarity() {
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert!((similarity("Hello, world!", " hello world") - 1.0).abs() < f64::EPSILON);
+        assert!((similarity("", "...") - 1.0).abs() < f64::EPSILON);
+        assert!(similarity("abc", "xyz") < f64::EPSILON);
+    }
+}
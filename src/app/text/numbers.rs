@@ -0,0 +1,419 @@
+use std::sync::OnceLock;
+
+use regex::Regex;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Units,
+    Teens,
+    Tens,
+    Hundred,
+    Scale,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct NumberWord {
+    value: u64,
+    kind: Kind,
+    ordinal: bool,
+}
+
+fn number_word(word: &str) -> Option<NumberWord> {
+    const CARDINALS: [(&str, u64, Kind); 32] = [
+        ("zero", 0, Kind::Units),
+        ("one", 1, Kind::Units),
+        ("two", 2, Kind::Units),
+        ("three", 3, Kind::Units),
+        ("four", 4, Kind::Units),
+        ("five", 5, Kind::Units),
+        ("six", 6, Kind::Units),
+        ("seven", 7, Kind::Units),
+        ("eight", 8, Kind::Units),
+        ("nine", 9, Kind::Units),
+        ("ten", 10, Kind::Teens),
+        ("eleven", 11, Kind::Teens),
+        ("twelve", 12, Kind::Teens),
+        ("thirteen", 13, Kind::Teens),
+        ("fourteen", 14, Kind::Teens),
+        ("fifteen", 15, Kind::Teens),
+        ("sixteen", 16, Kind::Teens),
+        ("seventeen", 17, Kind::Teens),
+        ("eighteen", 18, Kind::Teens),
+        ("nineteen", 19, Kind::Teens),
+        ("twenty", 20, Kind::Tens),
+        ("thirty", 30, Kind::Tens),
+        ("forty", 40, Kind::Tens),
+        ("fifty", 50, Kind::Tens),
+        ("sixty", 60, Kind::Tens),
+        ("seventy", 70, Kind::Tens),
+        ("eighty", 80, Kind::Tens),
+        ("ninety", 90, Kind::Tens),
+        ("hundred", 100, Kind::Hundred),
+        ("thousand", 1_000, Kind::Scale),
+        ("million", 1_000_000, Kind::Scale),
+        ("billion", 1_000_000_000, Kind::Scale),
+    ];
+
+    const ORDINALS: [(&str, &str); 9] = [
+        ("first", "one"),
+        ("second", "two"),
+        ("third", "three"),
+        ("fifth", "five"),
+        ("eighth", "eight"),
+        ("ninth", "nine"),
+        ("twelfth", "twelve"),
+        ("twentieth", "twenty"),
+        ("thirtieth", "thirty"),
+    ];
+
+    let lookup = |w: &str| {
+        CARDINALS
+            .iter()
+            .find(|(name, _, _)| *name == w)
+            .map(|&(_, value, kind)| (value, kind))
+    };
+
+    if let Some((value, kind)) = lookup(word) {
+        return Some(NumberWord {
+            value,
+            kind,
+            ordinal: false,
+        });
+    }
+
+    let cardinal = ORDINALS
+        .iter()
+        .find(|(name, _)| *name == word)
+        .map(|(_, cardinal)| (*cardinal).to_string())
+        .or_else(|| {
+            word.strip_suffix("ieth")
+                .map(|stem| format!("{stem}y"))
+                .or_else(|| word.strip_suffix("th").map(str::to_string))
+        })?;
+
+    lookup(&cardinal).map(|(value, kind)| NumberWord {
+        value,
+        kind,
+        ordinal: true,
+    })
+}
+
+impl Kind {
+    fn may_follow(self, prev: Option<Kind>) -> bool {
+        match self {
+            Kind::Units => matches!(prev, None | Some(Kind::Tens | Kind::Hundred | Kind::Scale)),
+            Kind::Teens | Kind::Tens => {
+                matches!(prev, None | Some(Kind::Hundred | Kind::Scale))
+            }
+            Kind::Hundred => matches!(prev, Some(Kind::Units | Kind::Teens)),
+            Kind::Scale => matches!(
+                prev,
+                Some(Kind::Units | Kind::Teens | Kind::Tens | Kind::Hundred)
+            ),
+        }
+    }
+}
+
+/// A run of number words found in a piece of text.
+#[derive(Debug, Clone, Default)]
+struct Spoken {
+    total: u64,
+    current: u64,
+    words: usize,
+    last: Option<Kind>,
+    ordinal: bool,
+    decimals: String,
+    /// Whether the words around the run suggest a year, so that two numbers may form one
+    year: bool,
+}
+
+impl Spoken {
+    fn push(&mut self, word: NumberWord) -> bool {
+        if self.ordinal {
+            return false;
+        }
+
+        // Years are read in pairs: "in nineteen ninety nine", "since twenty twenty four"
+        let paired = self.year
+            && self.words == 1
+            && matches!(self.current, 11..=20)
+            && matches!(word.kind, Kind::Teens | Kind::Tens);
+        if paired {
+            self.current *= 100;
+            self.last = Some(Kind::Hundred);
+        }
+
+        if !word.kind.may_follow(self.last) {
+            return false;
+        }
+
+        // "zero" only stands on its own
+        if word.value == 0 && self.words > 0 {
+            return false;
+        }
+
+        match word.kind {
+            Kind::Units | Kind::Teens | Kind::Tens => self.current += word.value,
+            Kind::Hundred => self.current *= word.value,
+            Kind::Scale => {
+                self.total += self.current * word.value;
+                self.current = 0;
+            }
+        }
+
+        self.words += 1;
+        self.last = Some(word.kind);
+        self.ordinal = word.ordinal;
+        true
+    }
+
+    fn value(&self) -> u64 {
+        self.total + self.current
+    }
+
+    fn render(&self) -> String {
+        let value = self.value();
+        if !self.decimals.is_empty() {
+            return format!("{value}.{}", self.decimals);
+        }
+
+        if !self.ordinal {
+            return value.to_string();
+        }
+
+        let suffix = match (value % 10, value % 100) {
+            (_, 11..=13) => "th",
+            (1, _) => "st",
+            (2, _) => "nd",
+            (3, _) => "rd",
+            _ => "th",
+        };
+        format!("{value}{suffix}")
+    }
+}
+
+/// Words after which a pair of numbers is read as a year.
+const YEAR_CONTEXT: [&str; 10] = [
+    "in", "since", "year", "of", "from", "until", "till", "circa", "during", "by",
+];
+
+/// Words after which an hour and minutes are read as a time of day.
+const CLOCK_CONTEXT: [&str; 6] = ["at", "around", "by", "until", "till", "before"];
+
+/// Parses the minutes of a time of day such as "thirty" or "forty five" from `words`,
+/// returning them with the number of words used.
+fn minutes(words: &[&str]) -> Option<(u64, usize)> {
+    let first = words.first().and_then(|w| number_word(w))?;
+    match first.kind {
+        _ if first.ordinal => None,
+        Kind::Teens => Some((first.value, 1)),
+        Kind::Tens if first.value < 60 => {
+            let units = words
+                .get(1)
+                .and_then(|w| number_word(w))
+                .filter(|w| w.kind == Kind::Units && w.value > 0 && !w.ordinal);
+            Some(units.map_or((first.value, 1), |units| (first.value + units.value, 2)))
+        }
+        _ => None,
+    }
+}
+
+fn words_re() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"[A-Za-z]+(?:-[A-Za-z]+)*").unwrap())
+}
+
+/// Converts spelled-out numbers, ordinals and a few common units to their written forms.
+///
+/// "twenty three percent" becomes "23%", "five hundred dollars" becomes "$500" and "the
+/// twenty first" becomes "the 21st". Following common captioning style, single-word numbers
+/// below ten are left spelled out unless a unit or another number is next to them.
+///
+/// Pairs of numbers are only read as a year after a word such as "in" or "since" ("in nineteen
+/// ninety nine" becomes "in 1999"), and an hour and minutes are only read as a time of day after
+/// a word such as "at" or before "am" or "pm" ("at seven thirty" becomes "at 7:30").
+pub fn normalize(text: &str) -> String {
+    let words: Vec<_> = words_re().find_iter(text).collect();
+    let separated_by_space = |i: usize| {
+        text[words[i - 1].end()..words[i].start()]
+            .chars()
+            .all(char::is_whitespace)
+    };
+    let lower = |i: usize| words[i].as_str().to_lowercase();
+    let follows = |i: usize, context: &[&str]| {
+        i > 0 && separated_by_space(i) && context.contains(&lower(i - 1).as_str())
+    };
+    let is_number = |i: usize| number_word(&lower(i)).is_some_and(|w| !w.ordinal);
+
+    let mut out = String::with_capacity(text.len());
+    let mut copied = 0;
+    let mut i = 0;
+
+    while i < words.len() {
+        if let Some((clock, end)) = clock_time(&words, i, lower, separated_by_space)
+            .filter(|&(_, end)| follows(i, &CLOCK_CONTEXT) || is_meridiem(&words, end, lower))
+        {
+            out.push_str(&text[copied..words[i].start()]);
+            out.push_str(&clock);
+            copied = words[end - 1].end();
+            i = end;
+            continue;
+        }
+
+        let mut spoken = Spoken {
+            year: follows(i, &YEAR_CONTEXT),
+            ..Spoken::default()
+        };
+        // One past the last word that was part of the number
+        let mut j = i;
+        let mut k = i;
+
+        while k < words.len() && (k == i || separated_by_space(k)) {
+            let lower = lower(k);
+
+            // "one hundred and five"
+            if lower == "and" && matches!(spoken.last, Some(Kind::Hundred | Kind::Scale)) {
+                k += 1;
+                continue;
+            }
+
+            let mut attempt = spoken.clone();
+            let accepted = lower
+                .split('-')
+                .all(|part| number_word(part).is_some_and(|w| attempt.push(w)));
+            if !accepted {
+                break;
+            }
+
+            spoken = attempt;
+            k += 1;
+            j = k;
+        }
+
+        if spoken.words == 0 {
+            i += 1;
+            continue;
+        }
+
+        // "three point five"
+        if !spoken.ordinal && j + 1 < words.len() && lower(j) == "point" && separated_by_space(j) {
+            let mut k = j + 1;
+            let mut decimals = String::new();
+            while k < words.len() && separated_by_space(k) {
+                match number_word(&lower(k)) {
+                    Some(NumberWord {
+                        value: value @ 0..=9,
+                        kind: Kind::Units,
+                        ordinal: false,
+                    }) => decimals.push_str(&value.to_string()),
+                    _ => break,
+                }
+                k += 1;
+            }
+            if !decimals.is_empty() {
+                spoken.decimals = decimals;
+                j = k;
+            }
+        }
+
+        let unit = (j < words.len() && separated_by_space(j) && !spoken.ordinal)
+            .then(|| lower(j))
+            .and_then(|w| match w.as_str() {
+                "percent" => Some(("", "%")),
+                "dollars" | "dollar" => Some(("$", "")),
+                "degrees" | "degree" => Some(("", "°")),
+                _ => None,
+            });
+
+        // "seven thirty" becomes "7 30" rather than "seven 30"
+        let beside_number = !spoken.ordinal
+            && ((i > 0 && separated_by_space(i) && is_number(i - 1))
+                || (j < words.len() && separated_by_space(j) && is_number(j)));
+        let trivial = spoken.words == 1
+            && spoken.value() < 10
+            && spoken.decimals.is_empty()
+            && !beside_number;
+        if trivial && unit.is_none() {
+            i = j;
+            continue;
+        }
+
+        out.push_str(&text[copied..words[i].start()]);
+        let (prefix, suffix) = unit.unwrap_or_default();
+        out.push_str(prefix);
+        out.push_str(&spoken.render());
+        out.push_str(suffix);
+
+        let end = if unit.is_some() { j + 1 } else { j };
+        copied = words[end - 1].end();
+        i = end;
+    }
+
+    out.push_str(&text[copied..]);
+    out
+}
+
+/// Reads an hour from one to twelve followed by its minutes, starting at `words[i]`, returning
+/// the time as `H:MM` with one past the last word it used.
+fn clock_time(
+    words: &[regex::Match],
+    i: usize,
+    lower: impl Fn(usize) -> String,
+    separated_by_space: impl Fn(usize) -> bool,
+) -> Option<(String, usize)> {
+    let hour = number_word(&lower(i)).filter(|w| !w.ordinal && matches!(w.value, 1..=12))?;
+    let following = (i + 1..words.len().min(i + 3))
+        .take_while(|&k| separated_by_space(k))
+        .map(lower)
+        .collect::<Vec<_>>();
+    let following = following.iter().map(String::as_str).collect::<Vec<_>>();
+    let (minutes, used) = minutes(&following)?;
+    Some((format!("{}:{minutes:02}", hour.value), i + 1 + used))
+}
+
+/// Whether `words[i]` is "am" or "pm".
+fn is_meridiem(words: &[regex::Match], i: usize, lower: impl Fn(usize) -> String) -> bool {
+    i < words.len() && matches!(lower(i).as_str(), "am" | "pm")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::normalize;
+
+    #[test]
+    fn converts_numbers_and_units() {
+        assert_eq!(normalize("twenty three percent"), "23%");
+        assert_eq!(normalize("five hundred dollars"), "$500");
+        assert_eq!(normalize("the twenty first"), "the 21st");
+        assert_eq!(normalize("three point five"), "3.5");
+        assert_eq!(normalize("one hundred and five"), "105");
+        assert_eq!(normalize("two cats"), "two cats");
+        assert_eq!(normalize("two three cats"), "2 3 cats");
+        assert_eq!(normalize("the first one"), "the first one");
+    }
+
+    #[test]
+    fn pairs_years_only_in_context() {
+        assert_eq!(normalize("in nineteen ninety nine"), "in 1999");
+        assert_eq!(normalize("since twenty twenty four"), "since 2024");
+        assert_eq!(normalize("ten twenty people"), "10 20 people");
+        assert_eq!(normalize("forty forty"), "40 40");
+        assert_eq!(normalize("in forty forty"), "in 40 40");
+    }
+
+    #[test]
+    fn reads_clock_times() {
+        assert_eq!(normalize("at seven thirty"), "at 7:30");
+        assert_eq!(normalize("seven forty five pm"), "7:45 pm");
+        assert_eq!(normalize("at ten fifteen"), "at 10:15");
+        assert_eq!(normalize("seven thirty"), "7 30");
+    }
+
+    #[test]
+    fn leaves_other_words_alone() {
+        assert_eq!(normalize(""), "");
+        assert_eq!(normalize("none of the above"), "none of the above");
+        assert_eq!(normalize("twenty-something"), "twenty-something");
+        assert_eq!(normalize("twenty-three"), "23");
+    }
+}
@@ -0,0 +1,119 @@
+use std::{
+    io::{self, Write},
+    process::{Command, Stdio},
+};
+
+/// An external command that restores punctuation, such as a wrapper around a punctuation model.
+///
+/// The command is run through `sh -c` once per batch of segments. It receives one segment per
+/// line on stdin and must print exactly one line per segment, in the same order, on stdout.
+#[derive(Debug, Clone)]
+pub struct PunctuateCmd {
+    cmd: String,
+}
+
+impl PunctuateCmd {
+    #[allow(clippy::unnecessary_wraps)]
+    pub fn parse(s: &str) -> Result<Self, String> {
+        Ok(Self { cmd: s.to_string() })
+    }
+
+    pub fn run(&self, texts: &[&str]) -> io::Result<Vec<String>> {
+        let mut child = Command::new("sh")
+            .arg("-c")
+            .arg(&self.cmd)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()?;
+
+        let input = texts
+            .iter()
+            .map(|text| text.trim().replace(['\n', '\r'], " ") + "\n")
+            .collect::<String>();
+
+        // Feed stdin from another thread so a chatty command can't deadlock on a full pipe
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+        let writer = std::thread::spawn(move || stdin.write_all(input.as_bytes()));
+
+        let output = child.wait_with_output()?;
+        let written = writer.join().expect("stdin writer panicked");
+
+        // A command that fails without reading its input breaks the pipe, which says less than
+        // its exit status
+        if !output.status.success() {
+            return Err(io::Error::other(format!(
+                "punctuation command exited with {}",
+                output.status
+            )));
+        }
+        written?;
+
+        let stdout = String::from_utf8(output.stdout)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let lines = stdout.lines().collect::<Vec<_>>();
+        if lines.len() != texts.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "punctuation command returned {} lines for {} segments",
+                    lines.len(),
+                    texts.len()
+                ),
+            ));
+        }
+
+        // Keep the original leading whitespace; it marks word boundaries between events
+        Ok(texts
+            .iter()
+            .zip(lines)
+            .map(|(text, line)| {
+                let indent = &text[..text.len() - text.trim_start().len()];
+                format!("{indent}{}", line.trim())
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PunctuateCmd;
+
+    fn cmd(s: &str) -> PunctuateCmd {
+        PunctuateCmd::parse(s).unwrap()
+    }
+
+    #[test]
+    fn keeps_the_leading_whitespace_of_each_segment() {
+        let out = cmd("sed 's/$/./'")
+            .run(&[" hello there", "again\n"])
+            .unwrap();
+        assert_eq!(out, [" hello there.", "again."]);
+    }
+
+    #[test]
+    fn joins_lines_within_a_segment() {
+        let out = cmd("cat").run(&["one\ntwo"]).unwrap();
+        assert_eq!(out, ["one two"]);
+    }
+
+    #[test]
+    fn reports_a_failing_command() {
+        // Enough input to fill the pipe, so writing it fails too
+        let text = "word ".repeat(100_000);
+        let err = cmd("exit 3").run(&[&text]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "punctuation command exited with exit status: 3"
+        );
+    }
+
+    #[test]
+    fn reports_a_line_count_mismatch() {
+        let err = cmd("head -n 1").run(&["a", "b"]).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        assert_eq!(
+            err.to_string(),
+            "punctuation command returned 1 lines for 2 segments"
+        );
+    }
+}
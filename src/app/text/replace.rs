@@ -0,0 +1,137 @@
+use regex::{Regex, RegexBuilder};
+
+/// A sed-style substitution such as `s/kuber netties/Kubernetes/gi`.
+///
+/// Any character may follow the `s` as the delimiter, and is escaped with a backslash where it
+/// appears in the pattern or replacement. The replacement may refer to capture groups as `\1`
+/// or `$1`. Flags are `g` to replace every match rather than the first, and `i` to ignore case.
+#[derive(Debug, Clone)]
+pub struct Substitution {
+    regex: Regex,
+    replacement: String,
+    global: bool,
+}
+
+impl Substitution {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let mut chars = s.chars();
+        let (Some('s'), Some(delimiter)) = (chars.next(), chars.next()) else {
+            return Err(format!("expected s/pattern/replacement/flags, found {s:?}"));
+        };
+
+        let mut fields = vec![String::new()];
+        let mut chars = chars.peekable();
+        while let Some(c) = chars.next() {
+            let field = fields.last_mut().expect("fields start non-empty");
+            match c {
+                '\\' if chars.next_if_eq(&delimiter).is_some() => field.push(delimiter),
+                '\\' => {
+                    field.push('\\');
+                    field.extend(chars.next());
+                }
+                c if c == delimiter => fields.push(String::new()),
+                c => field.push(c),
+            }
+        }
+
+        let [pattern, replacement, flags] = &fields[..] else {
+            return Err(format!(
+                "expected s{delimiter}pattern{delimiter}replacement{delimiter}flags, found {s:?}"
+            ));
+        };
+
+        let mut builder = RegexBuilder::new(pattern);
+        let mut global = false;
+        for flag in flags.chars() {
+            match flag {
+                'g' => global = true,
+                'i' => {
+                    builder.case_insensitive(true);
+                }
+                _ => return Err(format!("unknown substitution flag {flag:?}; use g or i")),
+            }
+        }
+
+        Ok(Self {
+            regex: builder.build().map_err(|e| e.to_string())?,
+            replacement: sed_groups(replacement),
+            global,
+        })
+    }
+
+    pub fn apply(&self, text: &str) -> String {
+        // A limit of 0 replaces every match
+        let limit = usize::from(!self.global);
+        self.regex
+            .replacen(text, limit, self.replacement.as_str())
+            .into_owned()
+    }
+}
+
+/// Rewrites sed's `\1` group references as `${1}`, and `\\` as a plain backslash.
+fn sed_groups(replacement: &str) -> String {
+    let mut out = String::with_capacity(replacement.len());
+    let mut chars = replacement.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, chars.peek()) {
+            ('\\', Some(&d)) if d.is_ascii_digit() => {
+                out.extend(['$', '{', d, '}']);
+                chars.next();
+            }
+            ('\\', Some('\\')) => {
+                out.push('\\');
+                chars.next();
+            }
+            (c, _) => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Substitution;
+
+    fn apply(spec: &str, text: &str) -> String {
+        Substitution::parse(spec).unwrap().apply(text)
+    }
+
+    #[test]
+    fn replaces_the_first_match_unless_global() {
+        assert_eq!(apply("s/a/o/", "banana"), "bonana");
+        assert_eq!(apply("s/a/o/g", "banana"), "bonono");
+    }
+
+    #[test]
+    fn takes_any_delimiter_and_flags() {
+        assert_eq!(apply("s|/|-|g", "a/b/c"), "a-b-c");
+        assert_eq!(apply("s/HELLO/hi/i", "Hello there"), "hi there");
+        assert_eq!(apply(r"s/(\w+) (\w+)/$2 $1/", "one two"), "two one");
+        assert_eq!(apply(r"s/(\w+) (\w+)/\2 \1/", "one two"), "two one");
+    }
+
+    #[test]
+    fn escaped_delimiters_are_literal() {
+        assert_eq!(apply(r"s/a\/b/x/", "a/b"), "x");
+    }
+
+    #[test]
+    fn explains_malformed_expressions() {
+        let err = |spec| Substitution::parse(spec).unwrap_err();
+        assert_eq!(err(""), r#"expected s/pattern/replacement/flags, found """#);
+        assert_eq!(
+            err("x/a/b/"),
+            r#"expected s/pattern/replacement/flags, found "x/a/b/""#
+        );
+        assert_eq!(
+            err("s/a/b"),
+            r#"expected s/pattern/replacement/flags, found "s/a/b""#
+        );
+        assert_eq!(
+            err("s|a|b"),
+            r#"expected s|pattern|replacement|flags, found "s|a|b""#
+        );
+        assert_eq!(err("s/a/b/q"), "unknown substitution flag 'q'; use g or i");
+        assert!(err("s/(/b/").contains("unclosed group"));
+    }
+}
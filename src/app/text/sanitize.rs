@@ -0,0 +1,116 @@
+/// How aggressively to clean up characters that a target format may not support.
+///
+/// Each level includes everything done by the levels before it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, clap::ValueEnum)]
+pub enum Sanitize {
+    /// Leaves text untouched
+    None,
+    /// Removes control characters and zero-width spaces, and turns tabs into spaces. Joiners
+    /// and directional marks are kept, since some scripts need them to render correctly
+    Control,
+    /// Replaces typographic punctuation with ASCII equivalents and removes emoji
+    Plain,
+    /// Removes anything that is still not ASCII
+    Ascii,
+}
+
+impl Sanitize {
+    pub fn apply(self, text: &str) -> String {
+        if self == Self::None {
+            return text.to_string();
+        }
+
+        let mut out = String::with_capacity(text.len());
+        for c in text.chars() {
+            if c == '\t' {
+                out.push(' ');
+                continue;
+            }
+
+            if is_invisible(c) {
+                continue;
+            }
+
+            if self >= Self::Plain {
+                if is_emoji(c) {
+                    continue;
+                }
+                if let Some(replacement) = plain_equivalent(c) {
+                    out.push_str(replacement);
+                    continue;
+                }
+            }
+
+            if self >= Self::Ascii && !c.is_ascii() {
+                continue;
+            }
+
+            out.push(c);
+        }
+        out
+    }
+}
+
+fn is_invisible(c: char) -> bool {
+    (c.is_control() && c != '\n') || matches!(c, '\u{200B}' | '\u{2060}' | '\u{FEFF}')
+}
+
+fn is_emoji(c: char) -> bool {
+    matches!(
+        c,
+        '\u{1F000}'..='\u{1FAFF}'
+            | '\u{2600}'..='\u{27BF}'
+            | '\u{2B00}'..='\u{2BFF}'
+            | '\u{FE00}'..='\u{FE0F}'
+            | '\u{E0020}'..='\u{E007F}'
+    )
+}
+
+fn plain_equivalent(c: char) -> Option<&'static str> {
+    Some(match c {
+        '\u{2018}' | '\u{2019}' | '\u{201A}' | '\u{201B}' | '\u{2032}' => "'",
+        '\u{201C}' | '\u{201D}' | '\u{201E}' | '\u{201F}' | '\u{2033}' | '«' | '»' => "\"",
+        '\u{2010}'..='\u{2015}' | '\u{2212}' => "-",
+        '\u{2026}' => "...",
+        '\u{00A0}' | '\u{2000}'..='\u{200A}' | '\u{202F}' | '\u{205F}' | '\u{3000}' => " ",
+        '\u{2022}' | '\u{00B7}' => "*",
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Sanitize;
+
+    #[test]
+    fn none_leaves_text_untouched() {
+        assert_eq!(Sanitize::None.apply("a\tb\u{200B}"), "a\tb\u{200B}");
+    }
+
+    #[test]
+    fn control_removes_invisible_characters_but_keeps_joiners_and_marks() {
+        assert_eq!(Sanitize::Control.apply("a\tb\u{200B}c\u{7}\n"), "a bc\n");
+        assert_eq!(Sanitize::Control.apply("\u{FEFF}café"), "café");
+        assert_eq!(
+            Sanitize::Control.apply("\u{200F}שלום\u{200D}"),
+            "\u{200F}שלום\u{200D}"
+        );
+    }
+
+    #[test]
+    fn plain_replaces_typography_and_drops_emoji() {
+        assert_eq!(
+            Sanitize::Plain.apply("\u{201C}It\u{2019}s\u{201D} \u{2014} wait\u{2026} 🎉"),
+            "\"It's\" - wait... "
+        );
+        assert_eq!(Sanitize::Plain.apply("café"), "café");
+    }
+
+    #[test]
+    fn ascii_drops_everything_else() {
+        assert_eq!(
+            Sanitize::Ascii.apply("café \u{2018}x\u{2019} 日本"),
+            "caf 'x' "
+        );
+    }
+}
@@ -0,0 +1,44 @@
+use unicode_normalization::UnicodeNormalization;
+
+use super::sanitize::Sanitize;
+
+/// A Unicode normalization form, so that text which looks the same compares the same.
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum Normalization {
+    /// Composes accents with their letters, leaving compatibility characters alone
+    Nfc,
+    /// Also replaces compatibility characters such as ligatures and full-width letters
+    Nfkc,
+}
+
+impl Normalization {
+    /// Normalizes `text`, first removing control and zero-width characters.
+    pub fn apply(self, text: &str) -> String {
+        let text = Sanitize::Control.apply(text);
+        match self {
+            Self::Nfc => text.nfc().collect(),
+            Self::Nfkc => text.nfkc().collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Normalization;
+
+    #[test]
+    fn nfc_composes_accents() {
+        assert_eq!(Normalization::Nfc.apply("cafe\u{301}"), "caf\u{E9}");
+        assert_eq!(Normalization::Nfc.apply("\u{FB01}ne"), "\u{FB01}ne");
+    }
+
+    #[test]
+    fn nfkc_replaces_compatibility_characters() {
+        assert_eq!(Normalization::Nfkc.apply("\u{FB01}ne \u{FF21}"), "fine A");
+    }
+
+    #[test]
+    fn strips_control_and_zero_width_characters() {
+        assert_eq!(Normalization::Nfc.apply("a\u{200B}b\u{0}\u{FEFF}"), "ab");
+    }
+}
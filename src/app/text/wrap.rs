@@ -0,0 +1,204 @@
+use std::collections::HashMap;
+
+use itertools::Itertools;
+
+/// Words that read badly at the end of a subtitle line, because they bind to what follows.
+const BINDING_WORDS: [&str; 20] = [
+    "a", "an", "the", "of", "to", "in", "on", "at", "for", "with", "by", "from", "into", "and",
+    "or", "but", "my", "your", "his", "her",
+];
+
+/// A subtitle layout of at most `lines` lines of `width` characters, written as `42x2`, or `42`
+/// for two lines.
+#[derive(Debug, Clone, Copy)]
+pub struct Wrap {
+    width: usize,
+    lines: usize,
+}
+
+impl Wrap {
+    pub fn new(width: usize, lines: usize) -> Self {
+        Self { width, lines }
+    }
+
+    /// Parses `WIDTHxLINES`, or a bare `WIDTH` for two lines.
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let (width, lines) = s.split_once(['x', 'X']).unwrap_or((s, "2"));
+        let number = |n: &str| match n.trim().parse::<usize>() {
+            Ok(0) | Err(_) => Err(format!("expected a positive number, found {n:?}")),
+            Ok(n) => Ok(n),
+        };
+        Ok(Self::new(number(width)?, number(lines)?))
+    }
+
+    /// Breaks `text` into as few lines as fit within the width, up to the number of lines
+    /// allowed, balancing their lengths. Text too long for every line to fit is still broken
+    /// into that many lines, each as short as possible.
+    pub fn apply(self, text: &str) -> String {
+        let content = text.trim();
+        let words = content.split_whitespace().collect::<Vec<_>>();
+        if content.chars().count() <= self.width || words.len() < 2 || self.lines < 2 {
+            return text.to_string();
+        }
+
+        let most = self.lines.min(words.len());
+        let breaks = (2..most)
+            .map(|lines| best_breaks(&words, self.width, lines))
+            .find(|(_, fits)| *fits)
+            .unwrap_or_else(|| best_breaks(&words, self.width, most))
+            .0;
+
+        let indent = &text[..text.len() - text.trim_start().len()];
+        let lines = [0]
+            .into_iter()
+            .chain(breaks)
+            .chain([words.len()])
+            .tuple_windows()
+            .map(|(from, to)| words[from..to].join(" "))
+            .join("\n");
+        format!("{indent}{lines}")
+    }
+}
+
+/// The word indices at which to start each line after the first, when breaking `words` into
+/// exactly `lines` lines, and whether every line fits within `max_width`.
+///
+/// Lines are kept under `max_width` where possible, and as close in length to their neighbours
+/// as possible. Breaking after punctuation is favoured, while single-word lines and breaks
+/// after articles, prepositions and conjunctions are penalised.
+fn best_breaks(words: &[&str], max_width: usize, lines: usize) -> (Vec<usize>, bool) {
+    let n = words.len();
+    let width = |from: usize, to: usize| {
+        words[from..to]
+            .iter()
+            .map(|w| w.chars().count())
+            .sum::<usize>()
+            + (to - from - 1)
+    };
+    let signed = |x: usize| i64::try_from(x).unwrap_or(i64::MAX);
+
+    // The cost of the line from word `from` to `to` on its own
+    let line_cost = |from: usize, to: usize| {
+        let mut cost = 1000 * signed(width(from, to).saturating_sub(max_width));
+        if n > 2 && to - from == 1 {
+            cost += 15;
+        }
+        if to < n {
+            let last = words[to - 1];
+            if last.ends_with([',', '.', ';', ':', '!', '?']) {
+                cost -= 10;
+            }
+            if BINDING_WORDS.contains(&last.to_lowercase().as_str()) {
+                cost += 20;
+            }
+        }
+        cost
+    };
+    // The cost of a line following the one before it, which should be about as long
+    let pair_cost = |before: usize, from: usize, to: usize| {
+        let (top, bottom) = (width(before, from), width(from, to));
+        // Prefer a shorter top line when everything else is equal
+        signed(top.abs_diff(bottom) * 2) + i64::from(top > bottom)
+    };
+
+    // For each line, the lowest cost of the lines up to one running from word `from` to `to`,
+    // and where the line before it started
+    let mut best: Vec<HashMap<(usize, usize), (i64, usize)>> = vec![HashMap::new(); lines];
+    best[0] = (1..n).map(|to| ((0, to), (line_cost(0, to), 0))).collect();
+    for line in 1..lines {
+        let last = line == lines - 1;
+        let spans = (1..n)
+            .flat_map(|from| (from + 1..=n).map(move |to| (from, to)))
+            .filter(|&(_, to)| (to == n) == last);
+        best[line] = spans
+            .filter_map(|(from, to)| {
+                let cheapest = (0..from)
+                    .filter_map(|before| {
+                        let (cost, _) = best[line - 1].get(&(before, from))?;
+                        Some((
+                            cost + pair_cost(before, from, to) + line_cost(from, to),
+                            before,
+                        ))
+                    })
+                    .min()?;
+                Some(((from, to), cheapest))
+            })
+            .collect();
+    }
+
+    let mut breaks = vec![];
+    let mut span = best[lines - 1]
+        .iter()
+        .map(|(&span, &(cost, _))| (cost, span))
+        .min()
+        .map(|(_, span)| span);
+    for line in (1..lines).rev() {
+        let Some((from, to)) = span else { break };
+        breaks.push(from);
+        span = best[line]
+            .get(&(from, to))
+            .map(|&(_, before)| (before, from));
+    }
+    breaks.reverse();
+
+    let fits = [0]
+        .iter()
+        .chain(&breaks)
+        .chain([&n])
+        .tuple_windows()
+        .all(|(&from, &to)| width(from, to) <= max_width);
+    (breaks, fits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Wrap;
+
+    #[test]
+    fn parses_width_and_lines() {
+        let wrap = Wrap::parse("42x3").unwrap();
+        assert_eq!((wrap.width, wrap.lines), (42, 3));
+        let wrap = Wrap::parse("32X2").unwrap();
+        assert_eq!((wrap.width, wrap.lines), (32, 2));
+        let wrap = Wrap::parse("42").unwrap();
+        assert_eq!((wrap.width, wrap.lines), (42, 2));
+    }
+
+    #[test]
+    fn explains_malformed_layouts() {
+        let err = |s| Wrap::parse(s).unwrap_err();
+        assert_eq!(err("wide"), r#"expected a positive number, found "wide""#);
+        assert_eq!(err("x2"), r#"expected a positive number, found """#);
+        assert_eq!(err("42x"), r#"expected a positive number, found """#);
+        assert_eq!(err("0x2"), r#"expected a positive number, found "0""#);
+        assert_eq!(err("42x0"), r#"expected a positive number, found "0""#);
+        assert_eq!(err("-1x2"), r#"expected a positive number, found "-1""#);
+    }
+
+    #[test]
+    fn leaves_short_text_alone() {
+        assert_eq!(Wrap::new(20, 2).apply(" short line"), " short line");
+        assert_eq!(Wrap::new(5, 2).apply("unbreakable"), "unbreakable");
+    }
+
+    #[test]
+    fn balances_lines_and_avoids_binding_words() {
+        let wrap = Wrap::new(20, 2);
+        assert_eq!(
+            wrap.apply(" one two three four five six"),
+            " one two three\nfour five six"
+        );
+        assert_eq!(
+            wrap.apply("we went to the old mill, and back"),
+            "we went to the old\nmill, and back"
+        );
+    }
+
+    #[test]
+    fn uses_more_lines_only_when_needed() {
+        let text = "one two three four five six seven eight";
+        assert_eq!(Wrap::new(30, 3).apply(text).lines().count(), 2);
+        assert_eq!(Wrap::new(15, 3).apply(text).lines().count(), 3);
+        assert_eq!(Wrap::new(4, 2).apply(text).lines().count(), 2);
+    }
+}
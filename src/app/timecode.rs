@@ -0,0 +1,209 @@
+//! Frame rates and SMPTE timecode for video editing formats.
+use std::fmt;
+
+/// A video frame rate as an exact fraction of frames per second.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameRate {
+    num: u32,
+    den: u32,
+}
+
+impl FrameRate {
+    pub const PAL: Self = Self { num: 25, den: 1 };
+
+    /// Parses integer rates like `25`, the NTSC rates `23.976`, `29.97` and `59.94`, and
+    /// fractions like `30000/1001`.
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let rate = match s.trim() {
+            "23.976" | "23.98" => Self {
+                num: 24000,
+                den: 1001,
+            },
+            "29.97" => Self {
+                num: 30000,
+                den: 1001,
+            },
+            "47.952" => Self {
+                num: 48000,
+                den: 1001,
+            },
+            "59.94" => Self {
+                num: 60000,
+                den: 1001,
+            },
+            s => {
+                let (num, den) = s.split_once('/').unwrap_or((s, "1"));
+                let parse = |n: &str| n.trim().parse::<u32>().ok().filter(|&n| n > 0);
+                let (Some(num), Some(den)) = (parse(num), parse(den)) else {
+                    return Err(format!("invalid frame rate {s:?}"));
+                };
+                Self { num, den }
+            }
+        };
+        Ok(rate)
+    }
+
+    /// The exact number of frames per second.
+    pub fn per_second(self) -> f64 {
+        f64::from(self.num) / f64::from(self.den)
+    }
+
+    /// The whole number of frames counted per timecode second.
+    pub fn timebase(self) -> u32 {
+        self.num.div_ceil(self.den)
+    }
+
+    /// Whether timecode at this rate skips frame numbers to stay in step with the clock, as is
+    /// conventional for 29.97 and 59.94.
+    pub fn drop_frame(self) -> bool {
+        self.den == 1001 && self.timebase().is_multiple_of(30)
+    }
+
+    /// The frame that `ms` falls closest to.
+    pub fn frames(self, ms: u32) -> u64 {
+        (u64::from(ms) * u64::from(self.num) + u64::from(self.den) * 500)
+            / (u64::from(self.den) * 1000)
+    }
+
+    /// The start of `frames` in milliseconds, rounded to the nearest one.
+    pub fn ms(self, frames: u64) -> u32 {
+        let ms =
+            (frames * u64::from(self.den) * 1000 + u64::from(self.num) / 2) / u64::from(self.num);
+        u32::try_from(ms).unwrap_or(u32::MAX)
+    }
+
+    /// Moves `ms` to the start of the frame it falls closest to.
+    pub fn snap(self, ms: u32) -> u32 {
+        self.ms(self.frames(ms))
+    }
+
+    /// The start of `frames` as a rational number of seconds, as used by FCPXML.
+    pub fn rational(self, frames: u64) -> String {
+        if frames == 0 {
+            return "0s".to_string();
+        }
+        format!("{}/{}s", frames * u64::from(self.den), self.num)
+    }
+
+    pub fn timecode(self, frames: u64) -> Timecode {
+        Timecode { frames, rate: self }
+    }
+}
+
+impl fmt::Display for FrameRate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.den == 1 {
+            write!(f, "{}", self.num)
+        } else {
+            write!(f, "{:.3}", self.per_second())
+        }
+    }
+}
+
+/// A frame count rendered as `HH:MM:SS:FF`, or `HH:MM:SS;FF` for drop-frame rates.
+pub struct Timecode {
+    frames: u64,
+    rate: FrameRate,
+}
+
+impl fmt::Display for Timecode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let base = u64::from(self.rate.timebase());
+        let mut frames = self.frames;
+
+        if self.rate.drop_frame() {
+            // Frame numbers 0 and 1 (0 to 3 at 59.94) are skipped at the start of every minute
+            // except each tenth
+            let dropped = base / 15;
+            let per_ten_minutes = base * 600 - dropped * 9;
+            let per_minute = base * 60 - dropped;
+            let tens = frames / per_ten_minutes;
+            let rest = frames % per_ten_minutes;
+            frames += dropped * 9 * tens;
+            if rest > dropped {
+                frames += dropped * ((rest - dropped) / per_minute);
+            }
+        }
+
+        let separator = if self.rate.drop_frame() { ';' } else { ':' };
+        write!(
+            f,
+            "{:02}:{:02}:{:02}{separator}{:02}",
+            frames / (base * 3600),
+            frames / (base * 60) % 60,
+            frames / base % 60,
+            frames % base
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FrameRate;
+
+    fn rate(s: &str) -> FrameRate {
+        FrameRate::parse(s).unwrap()
+    }
+
+    #[test]
+    fn parses_integer_ntsc_and_fractional_rates() {
+        assert_eq!(rate("25"), FrameRate::PAL);
+        assert_eq!(rate("29.97"), rate("30000/1001"));
+        assert_eq!(rate("23.976").timebase(), 24);
+        assert_eq!(rate("59.94").to_string(), "59.940");
+    }
+
+    #[test]
+    fn reports_invalid_rates() {
+        assert_eq!(
+            FrameRate::parse("25/0").unwrap_err(),
+            "invalid frame rate \"25/0\""
+        );
+        assert_eq!(
+            FrameRate::parse(" 30.5 ").unwrap_err(),
+            "invalid frame rate \"30.5\""
+        );
+        assert!(FrameRate::parse("0").is_err());
+    }
+
+    #[test]
+    fn non_drop_timecode_counts_every_frame() {
+        let pal = FrameRate::PAL;
+        assert_eq!(pal.timecode(0).to_string(), "00:00:00:00");
+        assert_eq!(pal.timecode(25 * 3661 + 7).to_string(), "01:01:01:07");
+        assert!(!rate("23.976").drop_frame());
+        assert_eq!(rate("23.976").timecode(24).to_string(), "00:00:01:00");
+    }
+
+    #[test]
+    fn drop_frame_timecode_skips_numbers_each_minute() {
+        let ntsc = rate("29.97");
+        assert!(ntsc.drop_frame());
+        assert_eq!(ntsc.timecode(1799).to_string(), "00:00:59;29");
+        assert_eq!(ntsc.timecode(1800).to_string(), "00:01:00;02");
+        // Every tenth minute keeps its first frame numbers
+        assert_eq!(ntsc.timecode(17_982).to_string(), "00:10:00;00");
+        assert_eq!(ntsc.timecode(107_892).to_string(), "01:00:00;00");
+        assert_eq!(rate("59.94").timecode(3600).to_string(), "00:01:00;04");
+    }
+
+    #[test]
+    fn rounds_milliseconds_to_the_nearest_frame() {
+        let pal = FrameRate::PAL;
+        assert_eq!(pal.frames(1000), 25);
+        assert_eq!(pal.frames(19), 0);
+        assert_eq!(pal.frames(20), 1);
+        assert_eq!(rate("29.97").frames(60_000), 1798);
+    }
+
+    #[test]
+    fn snaps_to_the_start_of_the_nearest_frame() {
+        let pal = FrameRate::PAL;
+        assert_eq!(pal.ms(25), 1000);
+        assert_eq!(pal.snap(1019), 1000);
+        assert_eq!(pal.snap(1021), 1040);
+        let ntsc = rate("29.97");
+        assert_eq!(ntsc.snap(1000), 1001);
+        assert_eq!(ntsc.snap(u32::MAX), ntsc.ms(ntsc.frames(u32::MAX)));
+    }
+}
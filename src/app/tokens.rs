@@ -0,0 +1,23 @@
+//! Estimates of how many tokens a language model would see in a piece of text.
+use clap::ValueEnum;
+
+/// How to count tokens, for budgeting chunks to fit a model's context.
+#[derive(Debug, Clone, Copy, Default, ValueEnum)]
+pub enum Tokenizer {
+    /// About four characters per token, and at least one per word, like the BPE vocabularies
+    /// of most models for English text.
+    #[default]
+    Approx,
+    /// One token per whitespace-separated word.
+    Whitespace,
+}
+
+impl Tokenizer {
+    pub fn count(self, text: &str) -> usize {
+        let words = text.split_whitespace();
+        match self {
+            Self::Approx => words.map(|w| w.chars().count().div_ceil(4)).sum(),
+            Self::Whitespace => words.count(),
+        }
+    }
+}
@@ -0,0 +1,117 @@
+//! Round-trip checks for written output.
+use super::{
+    input::{lrc, srt, vtt},
+    output::Format,
+    podcast,
+};
+use crate::transcribe::{format_clock_value, Timing};
+
+#[derive(serde::Deserialize)]
+struct PlayerCues {
+    cues: Vec<Timing>,
+}
+
+/// Re-parses `written` as `format` and describes every way in which it differs from `expected`.
+///
+/// An empty list means the output round-trips without loss.
+pub fn lossy_conversions(
+    format: Format,
+    expected: &[Timing],
+    written: &[u8],
+) -> Result<Vec<String>, String> {
+    let (actual, trimmed) = match format {
+        Format::Csv => (
+            csv::Reader::from_reader(written)
+                .deserialize()
+                .collect::<Result<Vec<Timing>, _>>()
+                .map_err(|e| e.to_string())?,
+            false,
+        ),
+        Format::Json => (
+            serde_json::from_slice(written).map_err(|e| e.to_string())?,
+            false,
+        ),
+        Format::JsonLines => (
+            serde_json::Deserializer::from_slice(written)
+                .into_iter()
+                .collect::<Result<Vec<Timing>, _>>()
+                .map_err(|e| e.to_string())?,
+            false,
+        ),
+        Format::Player => (
+            serde_json::from_slice::<PlayerCues>(written)
+                .map_err(|e| e.to_string())?
+                .cues,
+            false,
+        ),
+        Format::Podcast => (
+            serde_json::from_slice::<podcast::Transcript>(written)
+                .map_err(|e| e.to_string())?
+                .into_timings(),
+            // Segment bodies are stored without surrounding whitespace
+            true,
+        ),
+        Format::Lrc { .. } => (
+            lrc::parse(&String::from_utf8_lossy(written))?,
+            // LRC only stores the trimmed content of each line
+            true,
+        ),
+        Format::Srt => (
+            srt::parse(&String::from_utf8_lossy(written))?,
+            // SRT only stores the trimmed content of each cue
+            true,
+        ),
+        Format::Vtt => (vtt::parse(&String::from_utf8_lossy(written))?, true),
+        Format::Anki => return Err("Anki output can't be read back".to_string()),
+        Format::Chapters => return Err("chapter lists can't be read back".to_string()),
+        Format::Edl => return Err("EDL output can't be read back".to_string()),
+        Format::Fcpxml => return Err("FCPXML output can't be read back".to_string()),
+        Format::Html => return Err("HTML output can't be read back".to_string()),
+        Format::Template => return Err("template output can't be read back".to_string()),
+        Format::Pretty { .. } => return Err("pretty output can't be read back".to_string()),
+    };
+
+    let mut lossy = vec![];
+    if actual.len() != expected.len() {
+        lossy.push(format!(
+            "wrote {} segments but read back {}",
+            expected.len(),
+            actual.len()
+        ));
+    }
+
+    for (i, (e, a)) in (1..).zip(expected.iter().zip(&actual)) {
+        let clock = |ms| format_clock_value(ms, None);
+        if e.start() != a.start() {
+            lossy.push(format!(
+                "segment {i}: start {} became {}",
+                clock(e.start()),
+                clock(a.start())
+            ));
+        }
+        if e.end() != a.end() {
+            lossy.push(format!(
+                "segment {i}: end {} became {}",
+                clock(e.end()),
+                clock(a.end())
+            ));
+        }
+
+        let text_changed = if trimmed {
+            e.content() != a.content()
+        } else {
+            e.text() != a.text()
+        };
+        if text_changed && e.content() != a.content() {
+            lossy.push(format!(
+                "segment {i}: text {:?} became {:?}",
+                e.content(),
+                a.content()
+            ));
+        } else if text_changed {
+            lossy.push(format!("segment {i}: surrounding whitespace changed"));
+        }
+    }
+
+    Ok(lossy)
+}
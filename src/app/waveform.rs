@@ -0,0 +1,97 @@
+//! Waveform peaks for interactive transcript players.
+use std::{
+    io::{self, Read},
+    path::Path,
+    process::{Command, Stdio},
+};
+
+use crate::transcribe::Timing;
+
+/// The rate at which media is decoded before peaks are measured.
+const SAMPLE_RATE: u32 = 8000;
+
+/// Min/max pairs of 8-bit samples, laid out like the JSON produced by `audiowaveform` so that
+/// players such as peaks.js can load them directly.
+#[derive(Debug, serde::Serialize)]
+pub struct Peaks {
+    version: u8,
+    channels: u8,
+    sample_rate: u32,
+    samples_per_pixel: u32,
+    bits: u8,
+    length: usize,
+    data: Vec<i8>,
+}
+
+impl Peaks {
+    /// Decodes `media` to mono PCM with `ffmpeg` and measures `per_second` peaks per second.
+    pub fn from_media(media: &Path, per_second: u32) -> io::Result<Self> {
+        let mut child = Command::new("ffmpeg")
+            .args(["-v", "error", "-i"])
+            .arg(media)
+            .args([
+                "-ac",
+                "1",
+                "-ar",
+                &SAMPLE_RATE.to_string(),
+                "-f",
+                "s16le",
+                "-",
+            ])
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|e| io::Error::new(e.kind(), format!("couldn't run ffmpeg: {e}")))?;
+
+        let mut pcm = vec![];
+        child
+            .stdout
+            .take()
+            .expect("stdout is piped")
+            .read_to_end(&mut pcm)?;
+
+        let status = child.wait()?;
+        if !status.success() {
+            return Err(io::Error::other(format!(
+                "ffmpeg couldn't decode {}: {status}",
+                media.display()
+            )));
+        }
+
+        let samples = pcm
+            .chunks_exact(2)
+            .map(|b| i16::from_le_bytes([b[0], b[1]]));
+        Ok(Self::from_samples(samples, per_second))
+    }
+
+    fn from_samples(samples: impl Iterator<Item = i16>, per_second: u32) -> Self {
+        let samples_per_pixel = (SAMPLE_RATE / per_second.max(1)).max(1);
+
+        let mut data = vec![];
+        let samples = samples.collect::<Vec<_>>();
+        for pixel in samples.chunks(samples_per_pixel as usize) {
+            let min = pixel.iter().copied().min().unwrap_or_default();
+            let max = pixel.iter().copied().max().unwrap_or_default();
+            #[allow(clippy::cast_possible_truncation)]
+            data.extend([(min >> 8) as i8, (max >> 8) as i8]);
+        }
+
+        Self {
+            version: 2,
+            channels: 1,
+            sample_rate: SAMPLE_RATE,
+            samples_per_pixel,
+            bits: 8,
+            length: data.len() / 2,
+            data,
+        }
+    }
+}
+
+/// A single JSON document holding everything a transcript player needs.
+#[derive(Debug, serde::Serialize)]
+pub struct PlayerDocument {
+    pub cues: Vec<Timing>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub waveform: Option<Peaks>,
+}
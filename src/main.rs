@@ -21,8 +21,14 @@ fn main() {
 
     let outcome = match app.command() {
         Command::Transform(t) => {
-            let timings = t.read_data().expect("failed to read timings");
-            match t.process_to_output(timings) {
+            let result = match t.batch() {
+                Some(paths) => t.process_batch(paths),
+                None => t
+                    .read_data()
+                    .map_err(AppError::Io)
+                    .and_then(|timings| t.process_to_output(timings)),
+            };
+            match result {
                 Ok(_) => ProgramOutcome::Expected,
                 Err(AppError::Io(e)) if e.kind() == io::ErrorKind::BrokenPipe => {
                     ProgramOutcome::Expected
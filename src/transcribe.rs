@@ -1,4 +1,4 @@
-use std::{io, time::Duration};
+use std::{collections::VecDeque, io, num::NonZeroUsize, time::Duration};
 
 use itertools::Itertools;
 
@@ -10,24 +10,111 @@ use itertools::Itertools;
 /// sttx leans heavily on this fact for its functionality.
 ///
 /// ```
+/// use sttx::Timing;
+///
 /// let zero = Timing::default();
 /// let timing = Timing::new(0, 1000, "Hello, world!".to_string());
 ///
 /// assert_eq!(zero.combine(&timing), timing);
 /// ```
-#[derive(Debug, Clone, Default, serde::Deserialize, serde::Serialize)]
+#[derive(Debug, Clone, Default, PartialEq, serde::Deserialize, serde::Serialize)]
 pub struct Timing {
+    #[serde(deserialize_with = "deserialize_ms")]
     start: u32,
+    #[serde(deserialize_with = "deserialize_ms")]
     end: u32,
     text: String,
+    /// Word-level timings within this one, when the source provides them.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    words: Vec<Timing>,
+    /// The diarized speaker label, when the source provides one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    speaker: Option<String>,
+    /// How sure the recognizer was of the text, from 0 to 1, when the source says.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    confidence: Option<f32>,
 }
 
 impl Timing {
     pub fn new(start: u32, end: u32, text: String) -> Self {
-        Self { start, end, text }
+        Self {
+            start,
+            end,
+            text,
+            words: vec![],
+            speaker: None,
+            confidence: None,
+        }
+    }
+
+    pub fn with_text(self, text: String) -> Self {
+        Self { text, ..self }
+    }
+
+    pub fn with_words(self, words: Vec<Timing>) -> Self {
+        Self { words, ..self }
+    }
+
+    pub fn with_speaker(self, speaker: Option<String>) -> Self {
+        Self { speaker, ..self }
+    }
+
+    pub fn with_confidence(self, confidence: Option<f32>) -> Self {
+        Self { confidence, ..self }
     }
 }
 
+/// Converts a number of seconds, as used by many transcription tools, to milliseconds.
+///
+/// ```
+/// use sttx::ms_from_seconds;
+///
+/// assert_eq!(ms_from_seconds(1.3), 1300);
+/// assert_eq!(ms_from_seconds(0.0005), 1);
+/// assert_eq!(ms_from_seconds(-2.0), 0);
+/// ```
+pub fn ms_from_seconds(seconds: f64) -> u32 {
+    (seconds * 1000.0).round().clamp(0.0, f64::from(u32::MAX)) as u32
+}
+
+/// Accepts either a number of milliseconds or a clock value like `00:01:02,500`, which is how
+/// many subtitle tools export their timestamps.
+fn deserialize_ms<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<u32, D::Error> {
+    struct Visitor;
+
+    impl serde::de::Visitor<'_> for Visitor {
+        type Value = u32;
+
+        fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            f.write_str("milliseconds or a clock value")
+        }
+
+        fn visit_u64<E: serde::de::Error>(self, v: u64) -> Result<u32, E> {
+            u32::try_from(v).map_err(E::custom)
+        }
+
+        fn visit_i64<E: serde::de::Error>(self, v: i64) -> Result<u32, E> {
+            u32::try_from(v).map_err(E::custom)
+        }
+
+        fn visit_f64<E: serde::de::Error>(self, v: f64) -> Result<u32, E> {
+            Ok(v.round().clamp(0.0, f64::from(u32::MAX)) as u32)
+        }
+
+        fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<u32, E> {
+            let v = v.trim();
+            match v.parse::<u32>() {
+                Ok(ms) => Ok(ms),
+                Err(_) => parse_clock_value(v)
+                    .filter(|_| v.contains(':'))
+                    .ok_or_else(|| E::custom(format!("invalid timestamp {v:?}"))),
+            }
+        }
+    }
+
+    deserializer.deserialize_any(Visitor)
+}
+
 impl std::fmt::Display for Timing {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
@@ -44,6 +131,8 @@ impl std::fmt::Display for Timing {
 /// Iterators of Timing values may reduce to 0 or 1 Timing value.
 ///
 /// ```
+/// use sttx::Timing;
+///
 /// let data = vec![
 ///    Timing::new(0, 1000, "Hello".to_string()),
 ///    Timing::new(1000, 1000, ",".to_string()),
@@ -63,7 +152,6 @@ impl FromIterator<Timing> for Option<Timing> {
     }
 }
 
-#[allow(dead_code)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum ClockScale {
     Seconds,
@@ -74,8 +162,8 @@ pub enum ClockScale {
 /// Formats a total number of milliseconds into a human-readable clock value.
 ///
 /// ```
-/// use crate::transcribe::format_clock_value;
-/// use crate::transcribe::ClockScale::*;
+/// use sttx::format_clock_value;
+/// use sttx::ClockScale::*;
 ///
 /// // 10, 1000, 60000, 3600000
 ///
@@ -89,17 +177,17 @@ pub enum ClockScale {
 /// assert_eq!(format_clock_value(1000,      Some(Minutes)),  "0:01.00");
 /// assert_eq!(format_clock_value(1000,      Some(Hours)), "0:00:01.00");
 ///
-/// assert_eq!(format_clock_value(60e3,      None),           "1:00.00");
-/// assert_eq!(format_clock_value(60e3,      Some(Seconds)),    "60.00");
-/// assert_eq!(format_clock_value(60e3,      Some(Minutes)),  "1:00.00");
-/// assert_eq!(format_clock_value(60e3,      Some(Hours)), "0:01:00.00");
+/// assert_eq!(format_clock_value(60_000,    None),           "1:00.00");
+/// assert_eq!(format_clock_value(60_000,    Some(Seconds)),    "60.00");
+/// assert_eq!(format_clock_value(60_000,    Some(Minutes)),  "1:00.00");
+/// assert_eq!(format_clock_value(60_000,    Some(Hours)), "0:01:00.00");
 ///
-/// assert_eq!(format_clock_value(60 * 60e3, None),        "1:00:00.00");
-/// assert_eq!(format_clock_value(60 * 60e3, Some(Seconds)),  "3600.00");
-/// assert_eq!(format_clock_value(60 * 60e3, Some(Minutes)), "60:00.00");
-/// assert_eq!(format_clock_value(60 * 60e3, Some(Hours)), "1:00:00.00");
+/// assert_eq!(format_clock_value(3_600_000, None),         "60:00.00");
+/// assert_eq!(format_clock_value(3_600_000, Some(Seconds)),  "3600.00");
+/// assert_eq!(format_clock_value(3_600_000, Some(Minutes)), "60:00.00");
+/// assert_eq!(format_clock_value(3_600_000, Some(Hours)), "1:00:00.00");
 /// ```
-fn format_clock_value(total_ms: u32, min_clock_scale: Option<ClockScale>) -> String {
+pub fn format_clock_value(total_ms: u32, min_clock_scale: Option<ClockScale>) -> String {
     let min_clock_scale = min_clock_scale.unwrap_or(ClockScale::Minutes);
     let ms = total_ms % 1000;
     let s = total_ms / 1000;
@@ -113,6 +201,54 @@ fn format_clock_value(total_ms: u32, min_clock_scale: Option<ClockScale>) -> Str
     }
 }
 
+/// Parses a clock value such as `1:02:03.5`, `02:03,500` or `3.25` into milliseconds.
+///
+/// Both `.` and `,` are accepted as the decimal separator, and any number of fractional digits
+/// may be given (digits beyond milliseconds are truncated).
+///
+/// ```
+/// use sttx::parse_clock_value;
+///
+/// assert_eq!(parse_clock_value("00:00:01,000"), Some(1000));
+/// assert_eq!(parse_clock_value("1:02:03.5"),    Some(3_723_500));
+/// assert_eq!(parse_clock_value("02:03.040"),    Some(123_040));
+/// assert_eq!(parse_clock_value("3.25"),         Some(3250));
+/// assert_eq!(parse_clock_value("1:60"),         None);
+/// assert_eq!(parse_clock_value("9999999:00:00"), None);
+/// assert_eq!(parse_clock_value("soon"),         None);
+/// ```
+pub fn parse_clock_value(s: &str) -> Option<u32> {
+    let s = s.trim();
+    let (clock, fraction) = match s.rfind(['.', ',']) {
+        Some(i) => (&s[..i], &s[i + 1..]),
+        None => (s, ""),
+    };
+
+    if !fraction.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let ms = format!("{fraction:0<3}")[..3].parse::<u32>().ok()?;
+
+    let mut parts = clock.split(':').rev();
+    let mut total = 0;
+    for (i, part) in parts.by_ref().take(3).enumerate() {
+        if part.is_empty() || !part.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        let value = part.parse::<u32>().ok()?;
+        if i < 2 && i + 1 < clock.split(':').count() && value >= 60 {
+            return None;
+        }
+        total = value.checked_mul(60u32.pow(i as u32))?.checked_add(total)?;
+    }
+
+    if parts.next().is_some() {
+        return None;
+    }
+
+    total.checked_mul(1000)?.checked_add(ms)
+}
+
 impl Timing {
     #[allow(dead_code)]
     pub fn start(&self) -> u32 {
@@ -126,18 +262,56 @@ impl Timing {
 
     #[allow(dead_code)]
     pub fn duration(&self) -> u32 {
-        self.end - self.start
+        self.end.saturating_sub(self.start)
+    }
+
+    /// The text exactly as given, including any whitespace marking word boundaries.
+    pub fn text(&self) -> &str {
+        &self.text
     }
 
     pub fn content(&self) -> &str {
         self.text.trim()
     }
 
+    /// Word-level timings, empty unless the input format provides them.
+    pub fn words(&self) -> &[Timing] {
+        &self.words
+    }
+
+    pub fn speaker(&self) -> Option<&str> {
+        self.speaker.as_deref()
+    }
+
+    pub fn confidence(&self) -> Option<f32> {
+        self.confidence
+    }
+
+    /// The lower confidence of the two timings, so that a merged segment is as doubtful as its
+    /// least certain part.
+    fn lowest_confidence(&self, other: &Self) -> Option<f32> {
+        match (self.confidence, other.confidence) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
+    }
+
+    /// The speaker of both timings, which is lost once different speakers are merged.
+    fn shared_speaker(&self, other: &Self) -> Option<String> {
+        match (&self.speaker, &other.speaker) {
+            (Some(a), Some(b)) if a != b => None,
+            (a, b) => a.clone().or_else(|| b.clone()),
+        }
+    }
+
     pub fn combine(&self, other: &Self) -> Self {
         Self {
             start: self.start,
             end: other.end,
             text: format!("{}{}", self.text, other.text),
+            words: [&self.words[..], &other.words[..]].concat(),
+            speaker: self.shared_speaker(other),
+            confidence: self.lowest_confidence(other),
         }
     }
 
@@ -147,12 +321,258 @@ impl Timing {
             start: self.start,
             end: self.end,
             text: format!("{}{}", self.text, other.text),
+            words: [&self.words[..], &other.words[..]].concat(),
+            speaker: self.shared_speaker(other),
+            confidence: self.lowest_confidence(other),
         }
     }
 
     fn is_continuation(&self) -> bool {
         !self.text.chars().next().is_some_and(char::is_whitespace)
     }
+
+    /// Splits into one timing per whitespace-separated word, each keeping the whitespace before
+    /// it.
+    ///
+    /// Word timings are used when there's one per word; otherwise times are interpolated in
+    /// proportion to the length of each word.
+    fn split_words(&self) -> Vec<Timing> {
+        let mut tokens: Vec<&str> = vec![];
+        let mut token_start = 0;
+        let mut in_space = true;
+        for (i, c) in self.text.char_indices() {
+            if c.is_whitespace() && !in_space && i > token_start {
+                tokens.push(&self.text[token_start..i]);
+                token_start = i;
+            }
+            in_space = c.is_whitespace();
+        }
+        match tokens.last_mut() {
+            // Trailing whitespace stays with the last word
+            Some(last) if self.text[token_start..].trim().is_empty() => {
+                *last = &self.text[token_start - last.len()..];
+            }
+            _ => tokens.push(&self.text[token_start..]),
+        }
+
+        if tokens.len() < 2 {
+            return vec![self.clone()];
+        }
+
+        let lengths = tokens
+            .iter()
+            .map(|token| token.trim().chars().count() as u64)
+            .collect_vec();
+        let total = lengths.iter().sum::<u64>().max(1);
+        let at = |chars: u64| {
+            let offset = u64::from(self.duration()) * chars / total;
+            self.start + u32::try_from(offset).unwrap_or(u32::MAX)
+        };
+
+        let timed = self.words.len() == tokens.len();
+        let mut chars = 0;
+        tokens
+            .into_iter()
+            .zip(lengths)
+            .enumerate()
+            .map(|(i, (token, len))| {
+                let (start, end, words, confidence) = if timed {
+                    let word = &self.words[i];
+                    let confidence = word.confidence.or(self.confidence);
+                    (word.start, word.end, vec![word.clone()], confidence)
+                } else {
+                    (at(chars), at(chars + len), vec![], self.confidence)
+                };
+                chars += len;
+                Timing {
+                    start,
+                    end,
+                    text: token.to_string(),
+                    words,
+                    speaker: self.speaker.clone(),
+                    confidence,
+                }
+            })
+            .collect()
+    }
+}
+
+/// The unit in which JSON and CSV output give `start` and `end`.
+///
+/// ```
+/// use sttx::{IteratorExt, TimeUnit, Timing};
+///
+/// let data = vec![Timing::new(1000, 2500, " Hello".to_string())];
+///
+/// let mut json = vec![];
+/// data.into_iter().boxed().write_json(&mut json, TimeUnit::Seconds, None).unwrap();
+/// assert_eq!(
+///     String::from_utf8(json).unwrap(),
+///     r#"[{"start":1.0,"end":2.5,"text":" Hello"}]"#
+/// );
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TimeUnit {
+    /// Whole milliseconds, which is how timings are read back.
+    #[default]
+    Milliseconds,
+    /// Fractional seconds, as most web players and Python tools expect.
+    Seconds,
+}
+
+#[derive(serde::Serialize)]
+#[serde(untagged)]
+enum Stamp {
+    Milliseconds(u32),
+    Seconds(f64),
+}
+
+/// The optional columns written to CSV, which every row must share.
+#[derive(Debug, Clone, Copy)]
+struct CsvColumns {
+    speaker: bool,
+    confidence: bool,
+}
+
+/// A timing as written in a particular `TimeUnit`.
+#[derive(serde::Serialize)]
+struct Record<'a> {
+    start: Stamp,
+    end: Stamp,
+    text: &'a str,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    words: Vec<Record<'a>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    speaker: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    confidence: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tokens: Option<usize>,
+}
+
+/// Counts the tokens in a piece of text, for writers that report them.
+pub type CountTokens<'f> = &'f dyn Fn(&str) -> usize;
+
+impl TimeUnit {
+    fn stamp(self, ms: u32) -> Stamp {
+        match self {
+            Self::Milliseconds => Stamp::Milliseconds(ms),
+            Self::Seconds => Stamp::Seconds(f64::from(ms) / 1000.0),
+        }
+    }
+
+    fn record(self, t: &Timing) -> Record<'_> {
+        Record {
+            start: self.stamp(t.start),
+            end: self.stamp(t.end),
+            text: &t.text,
+            words: self.records(&t.words),
+            speaker: t.speaker(),
+            confidence: t.confidence(),
+            tokens: None,
+        }
+    }
+
+    /// Like `record`, but with the number of tokens in the text if they're counted.
+    fn counted_record<'t>(self, t: &'t Timing, tokens: Option<CountTokens>) -> Record<'t> {
+        Record {
+            tokens: tokens.map(|count| count(&t.text)),
+            ..self.record(t)
+        }
+    }
+
+    fn counted_records<'t>(
+        self,
+        timings: &'t [Timing],
+        tokens: Option<CountTokens>,
+    ) -> Vec<Record<'t>> {
+        timings
+            .iter()
+            .map(|t| self.counted_record(t, tokens))
+            .collect()
+    }
+
+    fn records(self, timings: &[Timing]) -> Vec<Record<'_>> {
+        timings.iter().map(|t| self.record(t)).collect()
+    }
+}
+
+/// Details of `SubRip` output that some players are particular about.
+#[derive(Debug, Clone)]
+pub struct SrtOptions {
+    /// Ends lines with `\r\n` instead of `\n`.
+    pub crlf: bool,
+    /// Starts the file with a UTF-8 byte order mark.
+    pub bom: bool,
+    /// The number given to the first cue.
+    pub first_index: usize,
+}
+
+impl Default for SrtOptions {
+    fn default() -> Self {
+        Self {
+            crlf: false,
+            bom: false,
+            first_index: 1,
+        }
+    }
+}
+
+/// The characters that end a sentence.
+///
+/// By default these are `.`, `!` and `?` and their CJK counterparts `。`, `！` and `？`. A
+/// trailing ellipsis (`...` or `…`) leaves a sentence open, unless `…` is one of the characters.
+///
+/// ```
+/// use sttx::SentenceEnds;
+///
+/// let ends = SentenceEnds::default();
+/// assert!(ends.is_end(" Hello."));
+/// assert!(ends.is_end(" 你好。"));
+/// assert!(!ends.is_end(" Well..."));
+/// assert!(!ends.is_end("."));
+///
+/// let ends = SentenceEnds::new(".…".chars());
+/// assert!(ends.is_end(" Well..."));
+/// assert!(!ends.is_end(" Really?"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct SentenceEnds {
+    chars: Vec<char>,
+}
+
+impl Default for SentenceEnds {
+    fn default() -> Self {
+        Self::new(".!?。！？".chars())
+    }
+}
+
+impl SentenceEnds {
+    pub fn new(chars: impl IntoIterator<Item = char>) -> Self {
+        Self {
+            chars: chars.into_iter().collect(),
+        }
+    }
+
+    /// Whether `s` ends a sentence. A terminator on its own doesn't.
+    pub fn is_end(&self, s: &str) -> bool {
+        if s.ends_with("...") || s.ends_with('…') {
+            return self.chars.contains(&'…');
+        }
+        s.chars()
+            .enumerate()
+            .last()
+            .is_some_and(|(i, c)| i > 0 && self.chars.contains(&c))
+    }
+
+    /// Whether `s` ends where a clause or sentence may end.
+    fn is_clause_end(&self, s: &str) -> bool {
+        let s = s.trim_end();
+        self.is_end(s)
+            || s.ends_with([',', ';', ':', '—', '–', '、', '，'])
+            || s.ends_with(" -")
+            || s == "-"
+    }
 }
 
 pub struct Iter<I>
@@ -162,9 +582,9 @@ where
     inner: I,
 }
 
-impl<'a, I> Iterator for Iter<I>
+impl<I> Iterator for Iter<I>
 where
-    I: Iterator<Item = Timing> + 'a,
+    I: Iterator<Item = Timing>,
 {
     type Item = Timing;
 
@@ -180,9 +600,83 @@ impl<'a, I> Iter<I>
 where
     I: Iterator<Item = Timing> + 'a,
 {
-    pub fn sentences(self) -> IterDyn<'a> {
-        self.batching(move |it| it.take_while_inclusive(|t| !is_sentence(&t.text)).collect())
+    /// Concatenates segments up to and including the next one that ends a sentence.
+    pub fn sentences(self, ends: SentenceEnds) -> IterDyn<'a> {
+        self.batching(move |it| it.take_while_inclusive(|t| !ends.is_end(&t.text)).collect())
+            .boxed()
+    }
+
+    /// Like `sentences`, but also ends segments at commas, semicolons, colons and dashes where
+    /// they would otherwise grow past `max_chars` characters. Segments that are already too
+    /// long are split at those marks, with their times divided in proportion to length.
+    ///
+    /// ```
+    /// use sttx::{IteratorExt, SentenceEnds, Timing};
+    ///
+    /// let data = vec![
+    ///     Timing::new(0, 1000, " Well, if you insist,".to_string()),
+    ///     Timing::new(1000, 2000, " we can go.".to_string()),
+    /// ];
+    ///
+    /// let clauses: Vec<String> = data
+    ///     .into_iter()
+    ///     .boxed()
+    ///     .clauses(20, SentenceEnds::default())
+    ///     .map(|t| t.content().to_string())
+    ///     .collect();
+    /// assert_eq!(clauses, ["Well, if you insist,", "we can go."]);
+    /// ```
+    pub fn clauses(self, max_chars: usize, ends: SentenceEnds) -> IterDyn<'a> {
+        let len = |t: &Timing| t.content().chars().count();
+        let split_ends = ends.clone();
+        self.peekable()
+            .batching(move |it| {
+                let mut acc = it.next()?;
+                while !ends.is_end(&acc.text) {
+                    let Some(next) = it.next_if(|next| {
+                        !ends.is_clause_end(&acc.text) || len(&acc) + len(next) < max_chars
+                    }) else {
+                        break;
+                    };
+                    acc = acc.combine(&next);
+                }
+                Some(acc)
+            })
             .boxed()
+            .split_words_into(move |words| {
+                let clauses = words
+                    .into_iter()
+                    .batching(|it| {
+                        let clause = it
+                            .take_while_inclusive(|w| !split_ends.is_clause_end(&w.text))
+                            .collect_vec();
+                        (!clause.is_empty()).then_some(clause)
+                    })
+                    .collect_vec();
+
+                let mut parts: Vec<Vec<Timing>> = vec![];
+                let mut part_len = 0;
+                for clause in clauses {
+                    let clause_len = clause.iter().map(|w| w.text.chars().count()).sum::<usize>();
+                    match parts.last_mut() {
+                        Some(part) if part_len + clause_len <= max_chars => {
+                            part_len += clause_len;
+                            part.extend(clause);
+                        }
+                        _ => {
+                            // A part's length doesn't count the whitespace it starts with
+                            let indent = clause[0]
+                                .text
+                                .chars()
+                                .take_while(|c| c.is_whitespace())
+                                .count();
+                            part_len = clause_len - indent;
+                            parts.push(clause);
+                        }
+                    }
+                }
+                parts
+            })
     }
 
     pub fn max_silence(self, max_silence: Duration) -> IterDyn<'a> {
@@ -191,14 +685,15 @@ where
                 let mut acc = it.next()?;
                 let mut total_silence = 0;
 
-                while it.peek().map_or(false, |next| {
-                    total_silence + next.start - acc.end < max_silence.as_millis() as u32
+                while it.peek().is_some_and(|next| {
+                    total_silence + next.start.saturating_sub(acc.end)
+                        < max_silence.as_millis() as u32
                 }) {
                     let Some(next) = it.next() else {
                         return Some(acc);
                     };
 
-                    total_silence += next.start - acc.end;
+                    total_silence += next.start.saturating_sub(acc.end);
 
                     acc = acc.combine(&next);
                 }
@@ -215,12 +710,86 @@ where
         .boxed()
     }
 
+    /// Like `by_gap`, but only keeps apart segments separated by one of the longest gaps: those
+    /// longer than `percentile` percent of all gaps between segments. Reads the whole input
+    /// first.
+    ///
+    /// ```
+    /// use sttx::{IteratorExt, Timing};
+    ///
+    /// let data = vec![
+    ///     Timing::new(0, 1000, " one".to_string()),
+    ///     Timing::new(1100, 2000, " two".to_string()),
+    ///     Timing::new(4000, 5000, " three".to_string()),
+    ///     Timing::new(5200, 6000, " four".to_string()),
+    /// ];
+    ///
+    /// let merged: Vec<Timing> = data.into_iter().boxed().by_gap_percentile(60.0).collect();
+    /// assert_eq!(merged[0], Timing::new(0, 2000, " one two".to_string()));
+    /// assert_eq!(merged[1], Timing::new(4000, 6000, " three four".to_string()));
+    /// ```
+    pub fn by_gap_percentile(self, percentile: f64) -> IterDyn<'a> {
+        let timings = self.collect_vec();
+        let mut gaps = timings
+            .iter()
+            .tuple_windows()
+            .map(|(a, b)| b.start.saturating_sub(a.end))
+            .collect_vec();
+        gaps.sort_unstable();
+
+        // The nearest-rank percentile; gaps up to and including it are merged across
+        let rank = (percentile.clamp(0.0, 100.0) / 100.0 * gaps.len() as f64).ceil() as usize;
+        let threshold = match rank.checked_sub(1) {
+            Some(i) => gaps[i] + 1,
+            None => 0,
+        };
+        timings
+            .into_iter()
+            .boxed()
+            .by_gap(Duration::from_millis(u64::from(threshold)))
+    }
+
+    /// Concatenates consecutive segments, except where `split` says the next one should be kept
+    /// apart from the segment built so far.
+    ///
+    /// ```
+    /// use sttx::{IteratorExt, Timing};
+    ///
+    /// let data = vec![
+    ///     Timing::new(0, 1000, " one".to_string()),
+    ///     Timing::new(1000, 2000, " two".to_string()),
+    ///     Timing::new(2000, 3000, " three".to_string()),
+    /// ];
+    ///
+    /// let merged: Vec<Timing> = data
+    ///     .into_iter()
+    ///     .boxed()
+    ///     .split_when(|acc, _| acc.duration() >= 2000)
+    ///     .collect();
+    /// assert_eq!(merged[0], Timing::new(0, 2000, " one two".to_string()));
+    /// assert_eq!(merged[1], Timing::new(2000, 3000, " three".to_string()));
+    /// ```
+    pub fn split_when<F>(self, mut split: F) -> IterDyn<'a>
+    where
+        F: FnMut(&Timing, &Timing) -> bool + 'a,
+    {
+        self.peekable()
+            .batching(move |it| {
+                let mut acc = it.next()?;
+                while let Some(next) = it.next_if(|next| !split(&acc, next)) {
+                    acc = acc.combine(&next);
+                }
+                Some(acc)
+            })
+            .boxed()
+    }
+
     pub fn by_gap(self, gap_size: Duration) -> IterDyn<'a> {
         self.peekable()
             .batching(move |it| {
                 let mut acc = it.next()?;
-                while it.peek().map_or(false, |next| {
-                    next.start - acc.end < gap_size.as_millis() as u32
+                while it.peek().is_some_and(|next| {
+                    next.start.saturating_sub(acc.end) < gap_size.as_millis() as u32
                 }) {
                     let Some(next) = it.next() else {
                         return Some(acc);
@@ -233,6 +802,41 @@ where
             .boxed()
     }
 
+    /// Gives the silences between segments that last longer than `min_gap`, in place of the
+    /// segments themselves, with text such as `silence 4.2s`.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use sttx::{IteratorExt, Timing};
+    ///
+    /// let data = vec![
+    ///     Timing::new(0, 1000, " one".to_string()),
+    ///     Timing::new(1500, 2000, " two".to_string()),
+    ///     Timing::new(6200, 7000, " three".to_string()),
+    /// ];
+    ///
+    /// let silences: Vec<Timing> = data
+    ///     .into_iter()
+    ///     .boxed()
+    ///     .silences(Duration::from_secs(2))
+    ///     .collect();
+    /// assert_eq!(silences, vec![Timing::new(2000, 6200, " silence 4.2s".to_string())]);
+    /// ```
+    pub fn silences(self, min_gap: Duration) -> IterDyn<'a> {
+        let min_gap = min_gap.as_millis() as u32;
+        let mut speech_end = None;
+        self.filter_map(move |t| {
+            // Overlapping segments may end before the ones they overlap
+            let end = speech_end.replace(t.end.max(speech_end.unwrap_or(0)))?;
+            let gap = t.start.saturating_sub(end);
+            (gap > min_gap).then(|| {
+                let text = format!(" silence {:.1}s", f64::from(gap) / 1000.0);
+                Timing::new(end, t.start, text)
+            })
+        })
+        .boxed()
+    }
+
     pub fn lasting(self, window_size: Duration) -> IterDyn<'a> {
         self.batching(move |it| {
             let mut acc = it.next()?;
@@ -248,25 +852,829 @@ where
         .boxed()
     }
 
+    /// Merges each segment lasting less than `min_duration` into whichever neighbor it's
+    /// closer to, repeating until it's long enough or has no neighbors left.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use sttx::{IteratorExt, Timing};
+    ///
+    /// let data = vec![
+    ///     Timing::new(0, 1000, " Hello".to_string()),
+    ///     Timing::new(1100, 1200, " there".to_string()),
+    ///     Timing::new(2000, 3000, " friend".to_string()),
+    /// ];
+    ///
+    /// let merged: Vec<Timing> = data
+    ///     .into_iter()
+    ///     .boxed()
+    ///     .min_duration(Duration::from_millis(300))
+    ///     .collect();
+    /// assert_eq!(merged[0], Timing::new(0, 1200, " Hello there".to_string()));
+    /// assert_eq!(merged[1], Timing::new(2000, 3000, " friend".to_string()));
+    /// ```
+    pub fn min_duration(self, min_duration: Duration) -> IterDyn<'a> {
+        let min_ms = min_duration.as_millis() as u32;
+        let mut it = self.peekable();
+        // The last segment long enough to keep, which later short ones may still join
+        let mut held: Option<Timing> = None;
+
+        std::iter::from_fn(move || 'segments: loop {
+            let Some(mut t) = it.next() else {
+                return held.take();
+            };
+
+            while t.duration() < min_ms {
+                let gap_before = held.as_ref().map(|prev| t.start.saturating_sub(prev.end));
+                let gap_after = it.peek().map(|next| next.start.saturating_sub(t.end));
+                match (gap_before, gap_after) {
+                    (Some(before), after) if after.is_none_or(|after| before <= after) => {
+                        held = held.take().map(|prev| prev.combine(&t));
+                        continue 'segments;
+                    }
+                    (_, Some(_)) => {
+                        let Some(next) = it.next() else {
+                            break;
+                        };
+                        t = t.combine(&next);
+                    }
+                    _ => break,
+                }
+            }
+
+            if let Some(prev) = held.replace(t) {
+                return Some(prev);
+            }
+        })
+        .boxed()
+    }
+
+    /// Collapses runs of consecutive segments that `same` considers repeats of the first into
+    /// that one, extended to span the whole run.
+    ///
+    /// ```
+    /// use sttx::{IteratorExt, Timing};
+    ///
+    /// let data = vec![
+    ///     Timing::new(0, 1000, " Thank you.".to_string()),
+    ///     Timing::new(1000, 2000, " Thank you.".to_string()),
+    ///     Timing::new(2000, 3000, " Bye.".to_string()),
+    /// ];
+    ///
+    /// let deduped: Vec<Timing> = data
+    ///     .into_iter()
+    ///     .boxed()
+    ///     .dedupe_by(|a, b| a.content() == b.content())
+    ///     .collect();
+    /// assert_eq!(deduped[0], Timing::new(0, 2000, " Thank you.".to_string()));
+    /// assert_eq!(deduped[1], Timing::new(2000, 3000, " Bye.".to_string()));
+    /// ```
+    pub fn dedupe_by<F>(self, mut same: F) -> IterDyn<'a>
+    where
+        F: FnMut(&Timing, &Timing) -> bool + 'a,
+    {
+        self.peekable()
+            .batching(move |it| {
+                let mut acc = it.next()?;
+                while let Some(next) = it.next_if(|next| same(&acc, next)) {
+                    acc.end = acc.end.max(next.end);
+                }
+                Some(acc)
+            })
+            .boxed()
+    }
+
+    /// Ends each segment no later than the next one starts, so that no two overlap.
+    ///
+    /// ```
+    /// use sttx::{IteratorExt, Timing};
+    ///
+    /// let data = vec![
+    ///     Timing::new(0, 1500, " Hello".to_string()),
+    ///     Timing::new(1000, 2000, " world".to_string()),
+    /// ];
+    ///
+    /// let clamped: Vec<Timing> = data.into_iter().boxed().clamp_overlaps().collect();
+    /// assert_eq!(clamped[0], Timing::new(0, 1000, " Hello".to_string()));
+    /// assert_eq!(clamped[1], Timing::new(1000, 2000, " world".to_string()));
+    /// ```
+    pub fn clamp_overlaps(self) -> IterDyn<'a> {
+        self.peekable()
+            .batching(|it| {
+                let mut t = it.next()?;
+                if let Some(next) = it.peek() {
+                    t.end = t.end.min(next.start).max(t.start);
+                    for w in &mut t.words {
+                        w.start = w.start.min(t.end);
+                        w.end = w.end.min(t.end);
+                    }
+                }
+                Some(t)
+            })
+            .boxed()
+    }
+
+    /// Merges runs of segments where each overlaps the run so far by more than `min_overlap`,
+    /// spanning the whole run.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use sttx::{IteratorExt, Timing};
+    ///
+    /// let data = vec![
+    ///     Timing::new(0, 1500, " Hello".to_string()),
+    ///     Timing::new(1000, 2000, " world".to_string()),
+    ///     Timing::new(2000, 3000, " again".to_string()),
+    /// ];
+    ///
+    /// let merged: Vec<Timing> = data
+    ///     .into_iter()
+    ///     .boxed()
+    ///     .merge_overlapping(Duration::ZERO)
+    ///     .collect();
+    /// assert_eq!(merged[0], Timing::new(0, 2000, " Hello world".to_string()));
+    /// assert_eq!(merged[1], Timing::new(2000, 3000, " again".to_string()));
+    /// ```
+    pub fn merge_overlapping(self, min_overlap: Duration) -> IterDyn<'a> {
+        let min_overlap = min_overlap.as_millis();
+        self.peekable()
+            .batching(move |it| {
+                let mut acc = it.next()?;
+                while let Some(next) =
+                    it.next_if(|next| u128::from(acc.end.saturating_sub(next.start)) > min_overlap)
+                {
+                    let end = acc.end.max(next.end);
+                    acc = Timing {
+                        end,
+                        ..acc.combine(&next)
+                    };
+                }
+                Some(acc)
+            })
+            .boxed()
+    }
+
+    /// Groups consecutive segments with the same speaker, so that each group can be processed
+    /// without merging across speakers.
+    pub fn speaker_turns(self) -> impl Iterator<Item = Vec<Timing>> + 'a {
+        self.peekable().batching(|it| {
+            let mut turn = vec![it.next()?];
+            while let Some(next) = it.next_if(|next| next.speaker == turn[0].speaker) {
+                turn.push(next);
+            }
+            Some(turn)
+        })
+    }
+
+    /// Keeps only the last `n` segments. Nothing is yielded until the input runs out.
+    ///
+    /// ```
+    /// use sttx::{IteratorExt, Timing};
+    ///
+    /// let data = (0..5).map(|i| Timing::new(i * 1000, i * 1000 + 500, format!(" {i}")));
+    ///
+    /// let last: Vec<Timing> = data.boxed().tail(2).collect();
+    /// assert_eq!(last[0], Timing::new(3000, 3500, " 3".to_string()));
+    /// assert_eq!(last[1], Timing::new(4000, 4500, " 4".to_string()));
+    /// ```
+    pub fn tail(mut self, n: usize) -> IterDyn<'a> {
+        let mut last: Option<VecDeque<Timing>> = None;
+        std::iter::from_fn(move || {
+            last.get_or_insert_with(|| {
+                let mut last = VecDeque::with_capacity(n);
+                for t in self.by_ref() {
+                    if last.len() == n {
+                        last.pop_front();
+                    }
+                    if n > 0 {
+                        last.push_back(t);
+                    }
+                }
+                last
+            })
+            .pop_front()
+        })
+        .boxed()
+    }
+
+    /// Groups segments into chunks lasting up to `window`, where each chunk starts by repeating
+    /// the segments from the last `overlap` of the one before. Every chunk has at least one new
+    /// segment, even if that makes it run over.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use sttx::{IteratorExt, Timing};
+    ///
+    /// let data = vec![
+    ///     Timing::new(0, 10_000, " a".to_string()),
+    ///     Timing::new(10_000, 20_000, " b".to_string()),
+    ///     Timing::new(20_000, 30_000, " c".to_string()),
+    ///     Timing::new(30_000, 40_000, " d".to_string()),
+    /// ];
+    ///
+    /// let chunks: Vec<Timing> = data
+    ///     .into_iter()
+    ///     .boxed()
+    ///     .windows(Duration::from_secs(25), Duration::from_secs(10))
+    ///     .collect();
+    /// assert_eq!(chunks.len(), 3);
+    /// assert_eq!(chunks[0], Timing::new(0, 20_000, " a b".to_string()));
+    /// assert_eq!(chunks[1], Timing::new(10_000, 30_000, " b c".to_string()));
+    /// assert_eq!(chunks[2], Timing::new(20_000, 40_000, " c d".to_string()));
+    /// ```
+    pub fn windows(self, window: Duration, overlap: Duration) -> IterDyn<'a> {
+        let window_ms = window.as_millis() as u32;
+        let overlap_ms = overlap.as_millis() as u32;
+        let mut it = self.peekable();
+        // Segments repeated from the end of the last chunk
+        let mut carried: VecDeque<Timing> = VecDeque::new();
+
+        std::iter::from_fn(move || {
+            carried.push_back(it.next()?);
+            let start = carried[0].start;
+            while let Some(next) = it.next_if(|next| next.end.saturating_sub(start) <= window_ms) {
+                carried.push_back(next);
+            }
+
+            let chunk = carried.iter().cloned().collect::<Option<Timing>>();
+            let end = carried.back().map_or(0, Timing::end);
+            carried.retain(|t| t.start >= end.saturating_sub(overlap_ms));
+            chunk
+        })
+        .boxed()
+    }
+
+    /// Concatenates segments while their text adds up to at most `max` tokens, as counted by
+    /// `count`. A segment with more tokens than that on its own is left whole.
+    ///
+    /// ```
+    /// use sttx::{IteratorExt, Timing};
+    ///
+    /// let data = vec![
+    ///     Timing::new(0, 1000, " one two".to_string()),
+    ///     Timing::new(1000, 2000, " three".to_string()),
+    ///     Timing::new(2000, 3000, " four five".to_string()),
+    /// ];
+    ///
+    /// let words = |text: &str| text.split_whitespace().count();
+    /// let chunks: Vec<Timing> = data.into_iter().boxed().max_tokens(3, words).collect();
+    /// assert_eq!(chunks[0], Timing::new(0, 2000, " one two three".to_string()));
+    /// assert_eq!(chunks[1], Timing::new(2000, 3000, " four five".to_string()));
+    /// ```
+    pub fn max_tokens<F>(self, max: usize, count: F) -> IterDyn<'a>
+    where
+        F: Fn(&str) -> usize + 'a,
+    {
+        self.peekable()
+            .batching(move |it| {
+                let mut acc = it.next()?;
+                let mut used = count(&acc.text);
+                while let Some(tokens) = it
+                    .peek()
+                    .map(|next| count(&next.text))
+                    .filter(|tokens| used + tokens <= max)
+                {
+                    let Some(next) = it.next() else {
+                        return Some(acc);
+                    };
+                    used += tokens;
+                    acc = acc.combine(&next);
+                }
+                Some(acc)
+            })
+            .boxed()
+    }
+
     pub fn chunks(self, chunk_count: usize) -> IterDyn<'a> {
         self.batching(move |it| it.take(chunk_count).collect())
             .boxed()
     }
 
-    pub fn write_csv<W: io::Write>(self, w: W) -> csv::Result<()> {
+    /// Splits segments of more than `max_words` words into as few parts as needed, of nearly
+    /// equal length.
+    ///
+    /// Parts take their times from word timings when there's one per word, and otherwise
+    /// divide the segment's time in proportion to their length.
+    ///
+    /// ```
+    /// use sttx::{IteratorExt, Timing};
+    ///
+    /// let data = vec![Timing::new(0, 1000, " one two three four".to_string())];
+    ///
+    /// let parts: Vec<Timing> = data.into_iter().boxed().max_words(2).collect();
+    /// assert_eq!(parts[0], Timing::new(0, 400, " one two".to_string()));
+    /// assert_eq!(parts[1], Timing::new(400, 1000, " three four".to_string()));
+    /// ```
+    pub fn max_words(self, max_words: usize) -> IterDyn<'a> {
+        let max_words = max_words.max(1);
+        self.split_words_into(move |mut words| {
+            let parts = words.len().div_ceil(max_words);
+            // The first `words.len() % parts` parts take one extra word
+            let (size, extra) = (words.len() / parts, words.len() % parts);
+            (0..parts)
+                .map(|i| words.drain(..size + usize::from(i < extra)).collect())
+                .collect()
+        })
+    }
+
+    /// Splits segments after every word that ends a sentence, so that no segment holds more
+    /// than one. Without word timings, times are divided in proportion to the length of each
+    /// part.
+    ///
+    /// ```
+    /// use sttx::{IteratorExt, SentenceEnds, Timing};
+    ///
+    /// let data = vec![Timing::new(0, 2100, " Hello there. How are you?".to_string())];
+    ///
+    /// let sentences: Vec<Timing> = data
+    ///     .into_iter()
+    ///     .boxed()
+    ///     .split_sentences(SentenceEnds::default())
+    ///     .collect();
+    /// assert_eq!(sentences[0], Timing::new(0, 1100, " Hello there.".to_string()));
+    /// assert_eq!(sentences[1], Timing::new(1100, 2100, " How are you?".to_string()));
+    /// ```
+    pub fn split_sentences(self, ends: SentenceEnds) -> IterDyn<'a> {
+        self.split_words_into(move |words| {
+            let mut parts: Vec<Vec<Timing>> = vec![vec![]];
+            for word in words {
+                let ends_sentence = ends.is_end(&word.text);
+                parts.last_mut().expect("parts start non-empty").push(word);
+                if ends_sentence {
+                    parts.push(vec![]);
+                }
+            }
+            parts
+        })
+    }
+
+    /// Splits segments longer than `max_chars` characters at word boundaries, filling each part
+    /// with as many words as fit. A word longer than `max_chars` is left whole.
+    ///
+    /// ```
+    /// use sttx::{IteratorExt, Timing};
+    ///
+    /// let data = vec![Timing::new(0, 1000, " one two three four".to_string())];
+    ///
+    /// let parts: Vec<String> = data
+    ///     .into_iter()
+    ///     .boxed()
+    ///     .max_chars(10)
+    ///     .map(|t| t.content().to_string())
+    ///     .collect();
+    /// assert_eq!(parts, ["one two", "three four"]);
+    /// ```
+    pub fn max_chars(self, max_chars: usize) -> IterDyn<'a> {
+        self.split_words_into(move |words| {
+            let mut parts: Vec<Vec<Timing>> = vec![];
+            let mut len = 0;
+            for word in words {
+                match parts.last_mut() {
+                    Some(part) if len + word.text.chars().count() <= max_chars => {
+                        len += word.text.chars().count();
+                        part.push(word);
+                    }
+                    _ => {
+                        len = word.content().chars().count();
+                        parts.push(vec![word]);
+                    }
+                }
+            }
+            parts
+        })
+    }
+
+    /// Extends each segment shown for less than `min_display` into the silence after it, stopping
+    /// where the next segment starts.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use sttx::{IteratorExt, Timing};
+    ///
+    /// let data = vec![
+    ///     Timing::new(0, 300, " Hi.".to_string()),
+    ///     Timing::new(1000, 1200, " Yes.".to_string()),
+    ///     Timing::new(3000, 4500, " Goodbye.".to_string()),
+    /// ];
+    ///
+    /// let padded: Vec<Timing> = data
+    ///     .into_iter()
+    ///     .boxed()
+    ///     .min_display(Duration::from_millis(1200))
+    ///     .collect();
+    /// assert_eq!(padded[0], Timing::new(0, 1000, " Hi.".to_string()));
+    /// assert_eq!(padded[1], Timing::new(1000, 2200, " Yes.".to_string()));
+    /// assert_eq!(padded[2], Timing::new(3000, 4500, " Goodbye.".to_string()));
+    /// ```
+    pub fn min_display(self, min_display: Duration) -> IterDyn<'a> {
+        let min_ms = min_display.as_millis() as u32;
+        self.peekable()
+            .batching(move |it| {
+                let mut t = it.next()?;
+                let limit = it.peek().map_or(u32::MAX, |next| next.start);
+                t.end = t.end.max(t.start.saturating_add(min_ms).min(limit));
+                Some(t)
+            })
+            .boxed()
+    }
+
+    /// Keeps segments to at most `cps` characters per second of display time.
+    ///
+    /// Segments that are too fast are first extended into the silence after them. Failing that,
+    /// they're split at the pauses between words, so that each part can be extended into the
+    /// pause that follows it.
+    ///
+    /// ```
+    /// use sttx::{IteratorExt, Timing};
+    ///
+    /// let data = vec![
+    ///     Timing::new(0, 1000, " The quick brown fox.".to_string()),
+    ///     Timing::new(3000, 4000, " Jumps.".to_string()),
+    /// ];
+    ///
+    /// let readable: Vec<Timing> = data.into_iter().boxed().max_cps(10.0).collect();
+    /// assert_eq!(readable[0], Timing::new(0, 2000, " The quick brown fox.".to_string()));
+    /// assert_eq!(readable[1], Timing::new(3000, 4000, " Jumps.".to_string()));
+    /// ```
+    pub fn max_cps(self, cps: f64) -> IterDyn<'a> {
+        let reading_time =
+            move |t: &Timing| (t.content().chars().count() as f64 * 1000.0 / cps).ceil() as u32;
+        let readable_by =
+            move |t: &Timing, end: u32| end.saturating_sub(t.start) >= reading_time(t);
+
+        self.peekable()
+            .batching(move |it| {
+                let t = it.next()?;
+                let limit = it.peek().map_or(u32::MAX, |next| next.start);
+
+                let mut parts: Vec<Timing> = vec![];
+                if readable_by(&t, limit) {
+                    parts.push(t);
+                } else {
+                    for word in t.split_words() {
+                        match parts.last_mut() {
+                            Some(part) if !readable_by(part, word.start) => {
+                                *part = part.combine(&word);
+                            }
+                            _ => parts.push(word),
+                        }
+                    }
+                }
+
+                let ends = parts.iter().skip(1).map(Timing::start).chain([limit]);
+                let ends = ends.collect_vec();
+                for (part, limit) in parts.iter_mut().zip(ends) {
+                    part.end = part
+                        .end
+                        .max(part.start.saturating_add(reading_time(part)).min(limit));
+                }
+                Some(parts)
+            })
+            .flatten()
+            .boxed()
+    }
+
+    /// Splits segments lasting longer than `max_duration` at the last word boundary before each
+    /// part would run over. A single word lasting longer is left whole, as is a word ending
+    /// before the part it would join starts.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use sttx::{IteratorExt, Timing};
+    ///
+    /// let data = vec![Timing::new(0, 8000, " one two three four".to_string())];
+    ///
+    /// let parts: Vec<Timing> = data
+    ///     .into_iter()
+    ///     .boxed()
+    ///     .split_lasting(Duration::from_secs(5))
+    ///     .collect();
+    /// assert_eq!(parts[0], Timing::new(0, 3200, " one two".to_string()));
+    /// assert_eq!(parts[1], Timing::new(3200, 8000, " three four".to_string()));
+    ///
+    /// let unordered = vec![Timing::new(0, 4000, " one two".to_string()).with_words(vec![
+    ///     Timing::new(3000, 4000, " one".to_string()),
+    ///     Timing::new(0, 1000, " two".to_string()),
+    /// ])];
+    /// let parts = unordered.into_iter().boxed().split_lasting(Duration::from_secs(5));
+    /// assert_eq!(parts.count(), 1);
+    /// ```
+    pub fn split_lasting(self, max_duration: Duration) -> IterDyn<'a> {
+        let max_ms = max_duration.as_millis() as u32;
+        self.split_words_into(move |words| {
+            let mut parts: Vec<Vec<Timing>> = vec![];
+            for word in words {
+                match parts.last_mut() {
+                    Some(part) if word.end.saturating_sub(part[0].start) <= max_ms => {
+                        part.push(word)
+                    }
+                    _ => parts.push(vec![word]),
+                }
+            }
+            parts
+        })
+    }
+
+    /// Splits every segment into one segment per word, using word timings when there's one per
+    /// word and otherwise dividing time in proportion to the length of each word.
+    ///
+    /// ```
+    /// use sttx::{IteratorExt, Timing};
+    ///
+    /// let data = vec![Timing::new(0, 1000, " one four".to_string())];
+    ///
+    /// let words: Vec<Timing> = data.into_iter().boxed().words().collect();
+    /// assert_eq!(words[0], Timing::new(0, 428, " one".to_string()));
+    /// assert_eq!(words[1], Timing::new(428, 1000, " four".to_string()));
+    /// ```
+    pub fn words(self) -> IterDyn<'a> {
+        self.flat_map(|t| t.split_words()).boxed()
+    }
+
+    /// Splits each segment into words and regroups them with `group`, which must keep them
+    /// in order.
+    fn split_words_into<F>(self, mut group: F) -> IterDyn<'a>
+    where
+        F: FnMut(Vec<Timing>) -> Vec<Vec<Timing>> + 'a,
+    {
+        self.flat_map(move |t| {
+            group(t.split_words())
+                .into_iter()
+                .filter_map(|part| part.into_iter().collect::<Option<Timing>>())
+        })
+        .boxed()
+    }
+
+    /// Rewrites the text of each segment, leaving its timing untouched.
+    pub fn map_text<F>(self, mut f: F) -> IterDyn<'a>
+    where
+        F: FnMut(&str) -> String + 'a,
+    {
+        self.map(move |t| Timing {
+            text: f(&t.text),
+            ..t
+        })
+        .boxed()
+    }
+
+    /// Rewrites every start and end time, including those of word timings.
+    pub fn map_times<F>(self, f: F) -> IterDyn<'a>
+    where
+        F: Fn(u32) -> u32 + 'a,
+    {
+        fn retime(t: Timing, f: &impl Fn(u32) -> u32) -> Timing {
+            Timing {
+                start: f(t.start),
+                end: f(t.end),
+                words: t.words.into_iter().map(|w| retime(w, f)).collect(),
+                ..t
+            }
+        }
+
+        self.map(move |t| retime(t, &f)).boxed()
+    }
+
+    /// Shifts every time back by the start of the first segment, so that it starts at zero.
+    ///
+    /// ```
+    /// use sttx::{IteratorExt, Timing};
+    ///
+    /// let data = vec![
+    ///     Timing::new(61_000, 62_000, " Hello".to_string()),
+    ///     Timing::new(62_500, 63_000, " world".to_string()),
+    /// ];
+    ///
+    /// let rebased: Vec<Timing> = data.into_iter().boxed().rebase().collect();
+    /// assert_eq!(rebased[0], Timing::new(0, 1000, " Hello".to_string()));
+    /// assert_eq!(rebased[1], Timing::new(1500, 2000, " world".to_string()));
+    /// ```
+    pub fn rebase(self) -> IterDyn<'a> {
+        let mut it = self.peekable();
+        let origin = it.peek().map_or(0, Timing::start);
+        it.boxed().map_times(move |ms| ms.saturating_sub(origin))
+    }
+
+    /// Keeps the segments that overlap the time range from `from` to `to`, trimmed to fit
+    /// within it, whatever order they come in.
+    ///
+    /// ```
+    /// use sttx::{IteratorExt, Timing};
+    ///
+    /// let data = vec![
+    ///     Timing::new(3000, 4000, " four".to_string()),
+    ///     Timing::new(0, 1000, " one".to_string()),
+    ///     Timing::new(2000, 3000, " three".to_string()),
+    ///     Timing::new(1000, 2000, " two".to_string()),
+    /// ];
+    ///
+    /// let excerpt: Vec<Timing> = data.into_iter().boxed().time_range(1500, 3000).collect();
+    /// assert_eq!(excerpt.len(), 2);
+    /// assert_eq!(excerpt[0], Timing::new(2000, 3000, " three".to_string()));
+    /// assert_eq!(excerpt[1], Timing::new(1500, 2000, " two".to_string()));
+    /// ```
+    pub fn time_range(self, from: u32, to: u32) -> IterDyn<'a> {
+        self.filter(move |t| t.start < to && t.end > from)
+            .map(move |mut t| {
+                t.start = t.start.max(from);
+                t.end = t.end.min(to);
+                t.words.retain(|w| w.end > from && w.start < to);
+                for w in &mut t.words {
+                    w.start = w.start.max(from);
+                    w.end = w.end.min(to);
+                }
+                t
+            })
+            .boxed()
+    }
+
+    /// Rewrites the text of segments in batches of up to `size`, for transformations that are
+    /// expensive to start. `f` must return exactly one text per text it receives, in order.
+    ///
+    /// The segments end at the first batch that `f` fails on, or that it returns the wrong
+    /// number of texts for; the error is passed to `on_error`.
+    pub fn map_text_batched<F, E>(
+        self,
+        size: NonZeroUsize,
+        mut f: F,
+        mut on_error: E,
+    ) -> IterDyn<'a>
+    where
+        F: FnMut(&[&str]) -> io::Result<Vec<String>> + 'a,
+        E: FnMut(io::Error) + 'a,
+    {
+        self.batching(move |it| {
+            let batch = it.take(size.get()).collect_vec();
+            if batch.is_empty() {
+                return None;
+            }
+
+            let texts = f(&batch.iter().map(|t| t.text.as_str()).collect_vec()).and_then(|texts| {
+                if texts.len() == batch.len() {
+                    Ok(texts)
+                } else {
+                    Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("got {} texts for {} segments", texts.len(), batch.len()),
+                    ))
+                }
+            });
+            let texts = match texts {
+                Ok(texts) => texts,
+                Err(e) => {
+                    on_error(e);
+                    return None;
+                }
+            };
+
+            Some(
+                batch
+                    .into_iter()
+                    .zip(texts)
+                    .map(|(t, text)| Timing { text, ..t })
+                    .collect_vec(),
+            )
+        })
+        .flatten()
+        .boxed()
+    }
+
+    /// Writes `speaker` and `confidence` columns when any timing has them, left empty for any
+    /// timing without.
+    ///
+    /// ```
+    /// use sttx::{IteratorExt, TimeUnit, Timing};
+    ///
+    /// let data = vec![
+    ///     Timing::new(0, 1000, " Hi".to_string()),
+    ///     Timing::new(1000, 2000, " there".to_string()).with_speaker(Some("B".to_string())),
+    ///     Timing::new(2000, 3000, " you".to_string()).with_confidence(Some(0.5)),
+    /// ];
+    ///
+    /// let mut csv = vec![];
+    /// data.into_iter().boxed().write_csv(&mut csv, TimeUnit::Milliseconds).unwrap();
+    /// assert_eq!(
+    ///     String::from_utf8(csv).unwrap(),
+    ///     "start,end,text,speaker,confidence\n\
+    ///      0,1000, Hi,,\n\
+    ///      1000,2000, there,B,\n\
+    ///      2000,3000, you,,0.5\n"
+    /// );
+    /// ```
+    pub fn write_csv<W: io::Write>(self, w: W, unit: TimeUnit) -> csv::Result<()> {
+        let timings = self.collect_vec();
+        let columns = CsvColumns {
+            speaker: timings.iter().any(|t| t.speaker.is_some()),
+            confidence: timings.iter().any(|t| t.confidence.is_some()),
+        };
+        timings
+            .into_iter()
+            .boxed()
+            .write_csv_records(w, unit, columns, false)
+    }
+
+    /// Like `write_csv`, but flushes each record as soon as it's written, for output that's
+    /// consumed while it's being produced. Since later timings aren't known yet, the `speaker`
+    /// and `confidence` columns are always written.
+    pub fn write_csv_streaming<W: io::Write>(self, w: W, unit: TimeUnit) -> csv::Result<()> {
+        let columns = CsvColumns {
+            speaker: true,
+            confidence: true,
+        };
+        self.write_csv_records(w, unit, columns, true)
+    }
+
+    fn write_csv_records<W: io::Write>(
+        self,
+        w: W,
+        unit: TimeUnit,
+        columns: CsvColumns,
+        flush_each: bool,
+    ) -> csv::Result<()> {
+        // CSV has no room for nested word timings
+        #[derive(serde::Serialize)]
+        struct Record<'a> {
+            start: Stamp,
+            end: Stamp,
+            text: &'a str,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            speaker: Option<&'a str>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            confidence: Option<String>,
+        }
+
         let mut wtr = csv::Writer::from_writer(w);
         for t in self {
-            wtr.serialize(t)?;
+            wtr.serialize(Record {
+                start: unit.stamp(t.start),
+                end: unit.stamp(t.end),
+                text: &t.text,
+                speaker: columns.speaker.then(|| t.speaker().unwrap_or_default()),
+                confidence: columns
+                    .confidence
+                    .then(|| t.confidence.map(|c| c.to_string()).unwrap_or_default()),
+            })?;
+            if flush_each {
+                wtr.flush()?;
+            }
         }
         wtr.flush()?;
         Ok(())
     }
 
-    pub fn write_json<W: io::Write>(self, w: W) -> serde_json::Result<()> {
-        serde_json::to_writer(w, &self.collect::<Vec<_>>())
+    /// Writes a JSON array of timings, each with the number of tokens in its text if `tokens`
+    /// counts them.
+    pub fn write_json<W: io::Write>(
+        self,
+        w: W,
+        unit: TimeUnit,
+        tokens: Option<CountTokens>,
+    ) -> serde_json::Result<()> {
+        let timings = self.collect_vec();
+        serde_json::to_writer(w, &unit.counted_records(&timings, tokens))
+    }
+
+    /// Like `write_json`, but indented with one timing field per line, for reading and diffing.
+    pub fn write_json_pretty<W: io::Write>(
+        self,
+        mut w: W,
+        unit: TimeUnit,
+        tokens: Option<CountTokens>,
+    ) -> serde_json::Result<()> {
+        let timings = self.collect_vec();
+        serde_json::to_writer_pretty(&mut w, &unit.counted_records(&timings, tokens))?;
+        w.write_all(b"\n").map_err(serde_json::Error::io)
     }
 
-    pub fn write_srt<W: io::Write>(self, mut w: W) -> io::Result<()> {
+    /// Writes one JSON object per line, so consumers can process timings as they arrive.
+    pub fn write_json_lines<W: io::Write>(
+        self,
+        mut w: W,
+        unit: TimeUnit,
+        tokens: Option<CountTokens>,
+    ) -> serde_json::Result<()> {
+        for t in self {
+            serde_json::to_writer(&mut w, &unit.counted_record(&t, tokens))?;
+            w.write_all(b"\n").map_err(serde_json::Error::io)?;
+        }
+        Ok(())
+    }
+
+    /// Writes `SubRip` subtitles, one numbered cue per timing.
+    ///
+    /// ```
+    /// use sttx::{IteratorExt, SrtOptions, Timing};
+    ///
+    /// let data = vec![Timing::new(1000, 2500, " Hello".to_string())];
+    /// let options = SrtOptions { crlf: true, first_index: 0, ..SrtOptions::default() };
+    ///
+    /// let mut srt = vec![];
+    /// data.into_iter().boxed().write_srt(&mut srt, &options).unwrap();
+    /// assert_eq!(
+    ///     String::from_utf8(srt).unwrap(),
+    ///     "0\r\n00:00:01,000 --> 00:00:02,500\r\nHello\r\n\r\n"
+    /// );
+    /// ```
+    pub fn write_srt<W: io::Write>(self, mut w: W, options: &SrtOptions) -> io::Result<()> {
         fn format_srt_value(total_ms: u32) -> String {
             let ms = total_ms % 1000;
             let s = total_ms / 1000;
@@ -276,29 +1684,153 @@ where
             format!("{:02}:{:02}:{:02},{:03}", h, m % 60, s % 60, ms)
         }
 
-        let mut i = 1;
-        for t in self {
-            writeln!(w, "{}", i)?;
-            writeln!(
+        let eol = if options.crlf { "\r\n" } else { "\n" };
+        if options.bom {
+            write!(w, "\u{feff}")?;
+        }
+
+        for (i, t) in (options.first_index..).zip(self) {
+            write!(w, "{i}{eol}")?;
+            write!(
                 w,
-                "{} --> {}",
+                "{} --> {}{eol}",
                 format_srt_value(t.start),
                 format_srt_value(t.end)
             )?;
-            writeln!(w, "{}\n", t.content())?;
-            i += 1;
+            write!(w, "{}{eol}{eol}", t.content().replace('\n', eol))?;
         }
         Ok(())
     }
-}
 
-const MAX_DURATION: Duration = Duration::from_millis(500);
+    /// Writes `WebVTT` subtitles, marking the speaker of each cue with a voice tag.
+    ///
+    /// ```
+    /// use sttx::{IteratorExt, Timing};
+    ///
+    /// let data = vec![
+    ///     Timing::new(1000, 2500, " Hello".to_string()).with_speaker(Some("Ana".to_string())),
+    ///     Timing::new(3000, 4000, " Fish & chips".to_string()),
+    /// ];
+    ///
+    /// let mut vtt = vec![];
+    /// data.into_iter().boxed().write_vtt(&mut vtt).unwrap();
+    /// assert_eq!(
+    ///     String::from_utf8(vtt).unwrap(),
+    ///     "WEBVTT\n\n00:00:01.000 --> 00:00:02.500\n<v Ana>Hello\n\n\
+    ///      00:00:03.000 --> 00:00:04.000\nFish &amp; chips\n\n"
+    /// );
+    /// ```
+    pub fn write_vtt<W: io::Write>(self, mut w: W) -> io::Result<()> {
+        fn format_vtt_value(total_ms: u32) -> String {
+            let s = total_ms / 1000;
+            let m = s / 60;
+            format!(
+                "{:02}:{:02}:{:02}.{:03}",
+                m / 60,
+                m % 60,
+                s % 60,
+                total_ms % 1000
+            )
+        }
+        fn escape(text: &str) -> String {
+            text.replace('&', "&amp;")
+                .replace('<', "&lt;")
+                .replace('>', "&gt;")
+        }
+
+        write!(w, "WEBVTT\n\n")?;
+        for t in self {
+            let start = format_vtt_value(t.start);
+            writeln!(w, "{start} --> {}", format_vtt_value(t.end))?;
+            if let Some(speaker) = t.speaker() {
+                write!(w, "<v {}>", escape(speaker))?;
+            }
+            write!(w, "{}\n\n", escape(t.content()))?;
+        }
+        Ok(())
+    }
+
+    /// Writes a standalone HTML page with one paragraph per timing.
+    ///
+    /// Each paragraph carries its times in milliseconds as `data-start` and `data-end`, and opens
+    /// with a clock value linking to its own `id`, so passages can be shared by URL.
+    pub fn write_html<W: io::Write>(self, mut w: W) -> io::Result<()> {
+        fn escape(text: &str) -> String {
+            text.replace('&', "&amp;")
+                .replace('<', "&lt;")
+                .replace('>', "&gt;")
+                .replace('"', "&quot;")
+        }
+
+        writeln!(w, "<!DOCTYPE html>")?;
+        writeln!(w, "<html>")?;
+        writeln!(w, "<head>")?;
+        writeln!(w, "<meta charset=\"utf-8\">")?;
+        writeln!(w, "<title>Transcript</title>")?;
+        writeln!(w, "</head>")?;
+        writeln!(w, "<body>")?;
+        for (i, t) in (1..).zip(self) {
+            let speaker = t
+                .speaker()
+                .map(|s| format!(" data-speaker=\"{}\"", escape(s)))
+                .unwrap_or_default();
+            writeln!(
+                w,
+                "<p id=\"t{i}\" data-start=\"{}\" data-end=\"{}\"{speaker}><a href=\"#t{i}\">{}</a> {}</p>",
+                t.start,
+                t.end,
+                format_clock_value(t.start, None),
+                escape(t.content())
+            )?;
+        }
+        writeln!(w, "</body>")?;
+        writeln!(w, "</html>")?;
+        Ok(())
+    }
+
+    /// Writes LRC lyrics with one `[mm:ss.xx]` line per timing.
+    ///
+    /// An empty timed line follows each timing that doesn't run into the next, so players clear
+    /// the lyrics during gaps. In enhanced mode, word timings are written as inline `<mm:ss.xx>`
+    /// tags.
+    pub fn write_lrc<W: io::Write>(self, mut w: W, enhanced: bool) -> io::Result<()> {
+        fn format_lrc_value(total_ms: u32) -> String {
+            let s = total_ms / 1000;
+            format!("{:02}:{:02}.{:02}", s / 60, s % 60, total_ms % 1000 / 10)
+        }
+
+        let mut timings = self.peekable();
+        while let Some(t) = timings.next() {
+            let text = if enhanced && !t.words.is_empty() {
+                t.words
+                    .iter()
+                    .map(|word| format!("<{}>{}", format_lrc_value(word.start), word.content()))
+                    .join(" ")
+            } else {
+                t.content().to_string()
+            };
+            writeln!(w, "[{}]{}", format_lrc_value(t.start), text)?;
+
+            let end = format_lrc_value(t.end);
+            let runs_into_next = timings
+                .peek()
+                .is_some_and(|next| format_lrc_value(next.start) == end);
+            if !runs_into_next && end != format_lrc_value(t.start) {
+                writeln!(w, "[{end}]")?;
+            }
+        }
+        Ok(())
+    }
+}
 
 pub trait IteratorExt<'a>: Sized + Iterator<Item = Timing>
 where
     Self: 'a,
 {
-    fn join_continuations(self) -> IterDyn<'a> {
+    /// Joins each timing whose text doesn't start with whitespace to the one before it, ending
+    /// each joined utterance at most `max_duration` after it starts.
+    fn join_continuations(self, max_duration: Option<Duration>) -> IterDyn<'a> {
+        let max_duration = max_duration.map(|d| u32::try_from(d.as_millis()).unwrap_or(u32::MAX));
         self.peekable()
             .batching(|it| {
                 // We're consuming at least one event
@@ -315,8 +1847,8 @@ where
             })
             .map(move |mut t| {
                 // limit duration of each "utterance" to something reasonable
-                if t.duration() > MAX_DURATION.as_millis() as u32 {
-                    t.end = t.start + MAX_DURATION.as_millis() as u32;
+                if let Some(max) = max_duration.filter(|&max| t.duration() > max) {
+                    t.end = t.start.saturating_add(max);
                 }
                 t
             })
@@ -331,11 +1863,3 @@ where
 }
 
 impl<'a, I: Iterator<Item = Timing> + 'a> IteratorExt<'a> for I {}
-
-#[inline]
-fn is_sentence(s: &str) -> bool {
-    s.chars()
-        .enumerate()
-        .last()
-        .map_or(false, |(i, c)| i > 0 && matches!(c, '.' | '!' | '?'))
-}